@@ -0,0 +1,298 @@
+use crate::decode::token::TokenState;
+use crate::models::types::AccountSnapshot;
+use serde::Serialize;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+
+pub const BPF_LOADER_UPGRADEABLE_ID: Pubkey =
+    pubkey!("BPFLoaderUpgradeab1e11111111111111111111111");
+
+/// `UpgradeableLoaderState::ProgramData { slot: u64, upgrade_authority_address: Option<Pubkey> }`,
+/// bincode-encoded after a u32 variant tag.
+const PROGRAM_DATA_TAG: u32 = 3;
+const PROGRAM_DATA_AUTHORITY_OFFSET: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AuthorityKind {
+    /// Upgrade authority of a BPF Upgradeable Loader ProgramData account.
+    UpgradeAuthority,
+    MintAuthority,
+    FreezeAuthority,
+    /// Owner of an SPL token account.
+    TokenAccountOwner,
+    /// Program that owns the account, e.g. after a System `Assign`.
+    ProgramOwner,
+}
+
+impl fmt::Display for AuthorityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuthorityKind::UpgradeAuthority => "Upgrade authority",
+            AuthorityKind::MintAuthority => "Mint authority",
+            AuthorityKind::FreezeAuthority => "Freeze authority",
+            AuthorityKind::TokenAccountOwner => "Token account owner",
+            AuthorityKind::ProgramOwner => "Program owner",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How serious a finding is. Authority changes are always `High`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A change of who controls an account. `None` means no authority (revoked).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthorityChange {
+    pub kind: AuthorityKind,
+    pub old: Option<Pubkey>,
+    pub new: Option<Pubkey>,
+    pub severity: Severity,
+}
+
+impl AuthorityChange {
+    /// Whether `reason`, from the plain diff, reports the same change as this finding.
+    pub fn covers(&self, reason: &str) -> bool {
+        let field = match self.kind {
+            AuthorityKind::ProgramOwner => return reason == "Owner changed",
+            AuthorityKind::UpgradeAuthority => return false,
+            AuthorityKind::MintAuthority => "mint.mint_authority",
+            AuthorityKind::FreezeAuthority => "mint.freeze_authority",
+            AuthorityKind::TokenAccountOwner => "token_account.owner",
+        };
+        reason
+            .strip_prefix(field)
+            .is_some_and(|rest| rest.starts_with(": "))
+    }
+}
+
+impl fmt::Display for AuthorityChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} changed: {} -> {}",
+            self.kind,
+            display_authority(&self.old),
+            display_authority(&self.new)
+        )
+    }
+}
+
+fn display_authority(authority: &Option<Pubkey>) -> String {
+    authority.map_or_else(|| "none".to_string(), |pubkey| pubkey.to_string())
+}
+
+/// Authority changes between two states of one account, from the account owner
+/// and from the well-known layouts that carry an authority.
+pub fn detect_authority_changes(
+    before: &AccountSnapshot,
+    after: &AccountSnapshot,
+) -> Vec<AuthorityChange> {
+    let mut changes = Vec::new();
+    let mut push = |kind, old: Option<Pubkey>, new: Option<Pubkey>| {
+        if old != new {
+            changes.push(AuthorityChange {
+                kind,
+                old,
+                new,
+                severity: Severity::High,
+            });
+        }
+    };
+
+    // An account that only appears or disappears has no owner to transfer
+    if before.exists() && after.exists() {
+        push(
+            AuthorityKind::ProgramOwner,
+            Some(before.owner),
+            Some(after.owner),
+        );
+    }
+
+    if let (Some(old), Some(new)) = (upgrade_authority(before), upgrade_authority(after)) {
+        push(AuthorityKind::UpgradeAuthority, old, new);
+    }
+
+    match (TokenState::unpack(before), TokenState::unpack(after)) {
+        (Some(TokenState::Mint(old)), Some(TokenState::Mint(new))) => {
+            push(
+                AuthorityKind::MintAuthority,
+                old.mint_authority,
+                new.mint_authority,
+            );
+            push(
+                AuthorityKind::FreezeAuthority,
+                old.freeze_authority,
+                new.freeze_authority,
+            );
+        }
+        (Some(TokenState::Account(old)), Some(TokenState::Account(new))) => {
+            push(
+                AuthorityKind::TokenAccountOwner,
+                Some(old.owner),
+                Some(new.owner),
+            );
+        }
+        _ => {}
+    }
+
+    changes
+}
+
+/// The upgrade authority of a ProgramData account: `Some(None)` when the
+/// program is immutable, `None` when the snapshot is not ProgramData.
+fn upgrade_authority(snapshot: &AccountSnapshot) -> Option<Option<Pubkey>> {
+    if snapshot.owner != BPF_LOADER_UPGRADEABLE_ID {
+        return None;
+    }
+
+    let data = &snapshot.data;
+    let tag = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+    if tag != PROGRAM_DATA_TAG {
+        return None;
+    }
+
+    match data.get(PROGRAM_DATA_AUTHORITY_OFFSET)? {
+        0 => Some(None),
+        1 => {
+            let start = PROGRAM_DATA_AUTHORITY_OFFSET + 1;
+            let bytes: [u8; 32] = data.get(start..start + 32)?.try_into().ok()?;
+            Some(Some(Pubkey::new_from_array(bytes)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::token::tests::{mint_data, token_account_data};
+    use crate::decode::token::TOKEN_PROGRAM_ID;
+
+    fn snapshot(owner: Pubkey, data: Vec<u8>) -> AccountSnapshot {
+        AccountSnapshot {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1_000_000,
+            owner,
+            executable: false,
+            data_len: data.len(),
+            data,
+            rent_epoch: 0,
+        }
+    }
+
+    fn program_data(authority: Option<Pubkey>) -> Vec<u8> {
+        let mut data = PROGRAM_DATA_TAG.to_le_bytes().to_vec();
+        data.extend(42u64.to_le_bytes());
+        match authority {
+            Some(pubkey) => {
+                data.push(1);
+                data.extend(pubkey.to_bytes());
+            }
+            None => data.extend([0; 33]),
+        }
+        data.extend([0xAA; 16]);
+        data
+    }
+
+    #[test]
+    fn detects_upgrade_authority_transfer() {
+        let old = Pubkey::new_unique();
+        let new = Pubkey::new_unique();
+        let before = snapshot(BPF_LOADER_UPGRADEABLE_ID, program_data(Some(old)));
+        let after = snapshot(BPF_LOADER_UPGRADEABLE_ID, program_data(Some(new)));
+
+        let changes = detect_authority_changes(&before, &after);
+        assert_eq!(
+            changes,
+            vec![AuthorityChange {
+                kind: AuthorityKind::UpgradeAuthority,
+                old: Some(old),
+                new: Some(new),
+                severity: Severity::High,
+            }]
+        );
+    }
+
+    #[test]
+    fn detects_program_made_immutable() {
+        let old = Pubkey::new_unique();
+        let before = snapshot(BPF_LOADER_UPGRADEABLE_ID, program_data(Some(old)));
+        let after = snapshot(BPF_LOADER_UPGRADEABLE_ID, program_data(None));
+
+        let changes = detect_authority_changes(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].new, None);
+        assert_eq!(
+            changes[0].to_string(),
+            format!("Upgrade authority changed: {} -> none", old)
+        );
+    }
+
+    #[test]
+    fn detects_mint_authority_revocation() {
+        let authority = Pubkey::new_unique();
+        let before = snapshot(TOKEN_PROGRAM_ID, mint_data(1_000, Some(authority)));
+        let after = snapshot(TOKEN_PROGRAM_ID, mint_data(1_000, None));
+
+        let changes = detect_authority_changes(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, AuthorityKind::MintAuthority);
+        assert_eq!(changes[0].old, Some(authority));
+    }
+
+    #[test]
+    fn detects_token_account_owner_change() {
+        let before_data = token_account_data(5, None, false);
+        let mut after_data = before_data.clone();
+        let new_owner = Pubkey::new_unique();
+        after_data[32..64].copy_from_slice(&new_owner.to_bytes());
+
+        let changes = detect_authority_changes(
+            &snapshot(TOKEN_PROGRAM_ID, before_data),
+            &snapshot(TOKEN_PROGRAM_ID, after_data),
+        );
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, AuthorityKind::TokenAccountOwner);
+        assert_eq!(changes[0].new, Some(new_owner));
+        assert!(changes[0].covers(&format!("token_account.owner: a -> {}", new_owner)));
+        assert!(!changes[0].covers("token_account.owner_note: 1 -> 2"));
+    }
+
+    #[test]
+    fn detects_assign() {
+        let program = Pubkey::new_unique();
+        let before = snapshot(Pubkey::default(), vec![]);
+        let after = snapshot(program, vec![]);
+
+        let changes = detect_authority_changes(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, AuthorityKind::ProgramOwner);
+        assert_eq!(changes[0].new, Some(program));
+    }
+
+    #[test]
+    fn unchanged_authorities_report_nothing() {
+        let data = mint_data(1, Some(Pubkey::new_unique()));
+        let before = snapshot(TOKEN_PROGRAM_ID, data.clone());
+        let after = snapshot(TOKEN_PROGRAM_ID, data);
+        assert!(detect_authority_changes(&before, &after).is_empty());
+    }
+}
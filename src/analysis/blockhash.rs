@@ -0,0 +1,107 @@
+use crate::analysis::engine::AnalysisResult;
+use serde::Serialize;
+
+/// Why a successful simulation says nothing about whether the transaction can land.
+pub const REPLACED_BLOCKHASH_NOTE: &str = "the simulation replaces the recent blockhash, \
+     so it succeeds even when the transaction's own blockhash has expired";
+
+/// Whether the transaction's recent blockhash still lets it land.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockhashStatus {
+    pub blockhash: String,
+    /// `isBlockhashValid` as answered by the RPC node.
+    pub valid: bool,
+    pub current_block_height: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_valid_block_height: Option<u64>,
+    /// Blocks left before the transaction expires, when `last_valid_block_height` is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_remaining: Option<u64>,
+}
+
+impl BlockhashStatus {
+    pub fn new(
+        blockhash: String,
+        valid: bool,
+        current_block_height: u64,
+        last_valid_block_height: Option<u64>,
+    ) -> Self {
+        let blocks_remaining = last_valid_block_height
+            .filter(|_| valid)
+            .map(|last| last.saturating_sub(current_block_height));
+
+        Self {
+            blockhash,
+            valid,
+            current_block_height,
+            last_valid_block_height,
+            blocks_remaining,
+        }
+    }
+
+    /// What the blockhash means for a retry, used as a classification reason.
+    pub fn reason(&self) -> String {
+        if !self.valid {
+            return format!(
+                "Recent blockhash expired: the original transaction can no longer land, \
+                 and a retry must be re-signed with a new blockhash ({})",
+                REPLACED_BLOCKHASH_NOTE
+            );
+        }
+
+        match self.blocks_remaining {
+            Some(blocks) => format!(
+                "Recent blockhash valid for {} more block(s): the original transaction may \
+                 still land, so wait for expiry before re-signing",
+                blocks
+            ),
+            None => "Recent blockhash still valid: the original transaction may still land, \
+                 so wait for expiry before re-signing"
+                .to_string(),
+        }
+    }
+}
+
+/// Record the blockhash status and its reason. The verdict is left alone: expiry
+/// decides whether the original can land, not what a re-signed retry would do.
+pub fn apply_blockhash_status(result: &mut AnalysisResult, status: BlockhashStatus) {
+    result.classification.reasons.push(status.reason());
+    result.blockhash = Some(status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_blocks_remaining() {
+        let status = BlockhashStatus::new("hash".into(), true, 1_000, Some(1_120));
+        assert_eq!(status.blocks_remaining, Some(120));
+        assert!(status.reason().contains("valid for 120 more block(s)"));
+    }
+
+    #[test]
+    fn valid_blockhash_says_original_may_land() {
+        let status = BlockhashStatus::new("hash".into(), true, 1_000, Some(1_001));
+        assert_eq!(
+            status.reason(),
+            "Recent blockhash valid for 1 more block(s): the original transaction may \
+             still land, so wait for expiry before re-signing"
+        );
+    }
+
+    #[test]
+    fn expired_blockhash_explains_replacement() {
+        let status = BlockhashStatus::new("hash".into(), false, 1_200, Some(1_120));
+        assert_eq!(status.blocks_remaining, None);
+        assert!(status.reason().starts_with("Recent blockhash expired"));
+        assert!(status.reason().contains(REPLACED_BLOCKHASH_NOTE));
+    }
+
+    #[test]
+    fn unknown_last_valid_height_has_no_count() {
+        let status = BlockhashStatus::new("hash".into(), true, 1_000, None);
+        assert_eq!(status.blocks_remaining, None);
+        assert!(status.reason().starts_with("Recent blockhash still valid"));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::analysis::cpi::InvocationNode;
+use crate::analysis::engine::AnalysisResult;
+use crate::tx::compute_budget::{ComputeBudgetInstruction, COMPUTE_BUDGET_PROGRAM_ID};
+use crate::tx::decode::DecodedTransaction;
+use crate::tx::fee::compute_unit_limit;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+/// Default headroom, as a percentage of the compute unit limit, below which a
+/// retry risks running out of compute.
+pub const DEFAULT_COMPUTE_MARGIN_PERCENT: u32 = 10;
+
+/// Compute units the transaction asked for against what the simulation used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComputeReport {
+    /// Limit set with `SetComputeUnitLimit`, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_limit: Option<u32>,
+    /// Limit the transaction runs under: the requested one or the per-instruction default.
+    pub effective_limit: u32,
+    /// Price set with `SetComputeUnitPrice`, in micro-lamports per unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units_consumed: Option<u64>,
+    /// Units left under the limit after the simulation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headroom: Option<u64>,
+    pub margin_percent: u32,
+    /// Headroom is below `margin_percent` of the limit.
+    pub below_margin: bool,
+    pub instructions: Vec<InstructionCompute>,
+}
+
+/// Units consumed by one top-level instruction, including its CPIs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InstructionCompute {
+    pub index: usize,
+    pub program_id: Pubkey,
+    /// `None` when the logs do not report it, e.g. the instruction never ran.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units_consumed: Option<u64>,
+}
+
+impl ComputeReport {
+    /// Compare the transaction's compute budget with the simulated consumption.
+    /// `invocations` are the top-level nodes of the call tree, in instruction order.
+    pub fn new(
+        tx: &DecodedTransaction,
+        units_consumed: Option<u64>,
+        invocations: &[InvocationNode],
+        margin_percent: u32,
+    ) -> Self {
+        let budget = tx.compute_budget_instructions();
+        let requested_limit = budget.iter().find_map(|ix| match ix {
+            ComputeBudgetInstruction::SetComputeUnitLimit(limit) => Some(*limit),
+            _ => None,
+        });
+        let unit_price = budget.iter().find_map(|ix| match ix {
+            ComputeBudgetInstruction::SetComputeUnitPrice(price) => Some(*price),
+            _ => None,
+        });
+        let effective_limit = compute_unit_limit(tx);
+
+        // Every instruction that ran logs one top-level invocation, in order
+        let instructions = tx
+            .instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, ix)| Some((index, tx.program_id(ix)?)))
+            .filter(|(_, program_id)| *program_id != COMPUTE_BUDGET_PROGRAM_ID)
+            .map(|(index, program_id)| InstructionCompute {
+                index,
+                program_id,
+                units_consumed: invocations
+                    .get(index)
+                    .filter(|node| node.program_id == program_id)
+                    .and_then(|node| node.compute_units_consumed),
+            })
+            .collect();
+
+        let headroom = units_consumed.map(|used| (effective_limit as u64).saturating_sub(used));
+        let below_margin = headroom.is_some_and(|headroom| {
+            headroom * 100 < effective_limit as u64 * margin_percent as u64
+        });
+
+        Self {
+            requested_limit,
+            effective_limit,
+            unit_price,
+            units_consumed,
+            headroom,
+            margin_percent,
+            below_margin,
+            instructions,
+        }
+    }
+
+    /// A warning when a retry could run out of compute, used as a classification reason.
+    pub fn reason(&self) -> Option<String> {
+        if !self.below_margin {
+            return None;
+        }
+        Some(format!(
+            "Compute headroom below {}%: {} of {} units consumed, so a retry against \
+             changed state may exceed the limit",
+            self.margin_percent,
+            self.units_consumed.unwrap_or_default(),
+            self.effective_limit
+        ))
+    }
+}
+
+/// Record the compute report and its warning. The verdict is left alone: running
+/// out of compute fails the retry without committing anything.
+pub fn apply_compute_report(result: &mut AnalysisResult, report: ComputeReport) {
+    if let Some(reason) = report.reason() {
+        result.classification.reasons.push(reason);
+    }
+    result.compute = Some(report);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::cpi::parse_invocations;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::Message;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+    fn decoded(instructions: &[Instruction]) -> DecodedTransaction {
+        let payer = Pubkey::new_unique();
+        let tx = Transaction::new_unsigned(Message::new(instructions, Some(&payer)));
+        VersionedTransaction::from(tx).into()
+    }
+
+    fn budget_ix(ix: ComputeBudgetInstruction) -> Instruction {
+        Instruction::new_with_bytes(COMPUTE_BUDGET_PROGRAM_ID, &ix.pack(), vec![])
+    }
+
+    fn invocation_logs(program: &Pubkey, consumed: u64) -> Vec<String> {
+        vec![
+            format!("Program {} invoke [1]", program),
+            format!(
+                "Program {} consumed {} of 200000 compute units",
+                program, consumed
+            ),
+            format!("Program {} success", program),
+        ]
+    }
+
+    #[test]
+    fn attributes_units_to_top_level_instructions() {
+        let program = Pubkey::new_unique();
+        let tx = decoded(&[
+            budget_ix(ComputeBudgetInstruction::SetComputeUnitLimit(50_000)),
+            budget_ix(ComputeBudgetInstruction::SetComputeUnitPrice(1_000)),
+            Instruction::new_with_bytes(program, &[], vec![]),
+        ]);
+        let mut logs = vec![
+            format!("Program {} invoke [1]", COMPUTE_BUDGET_PROGRAM_ID),
+            format!("Program {} success", COMPUTE_BUDGET_PROGRAM_ID),
+            format!("Program {} invoke [1]", COMPUTE_BUDGET_PROGRAM_ID),
+            format!("Program {} success", COMPUTE_BUDGET_PROGRAM_ID),
+        ];
+        logs.extend(invocation_logs(&program, 20_000));
+
+        let report = ComputeReport::new(
+            &tx,
+            Some(20_300),
+            &parse_invocations(&logs),
+            DEFAULT_COMPUTE_MARGIN_PERCENT,
+        );
+
+        assert_eq!(report.requested_limit, Some(50_000));
+        assert_eq!(report.effective_limit, 50_000);
+        assert_eq!(report.unit_price, Some(1_000));
+        assert_eq!(report.headroom, Some(29_700));
+        assert!(!report.below_margin);
+        assert_eq!(
+            report.instructions,
+            vec![InstructionCompute {
+                index: 2,
+                program_id: program,
+                units_consumed: Some(20_000),
+            }]
+        );
+        assert_eq!(report.reason(), None);
+    }
+
+    #[test]
+    fn warns_when_headroom_is_below_margin() {
+        let program = Pubkey::new_unique();
+        let tx = decoded(&[
+            budget_ix(ComputeBudgetInstruction::SetComputeUnitLimit(100_000)),
+            Instruction::new_with_bytes(program, &[], vec![]),
+        ]);
+
+        let report = ComputeReport::new(&tx, Some(95_000), &[], DEFAULT_COMPUTE_MARGIN_PERCENT);
+
+        assert_eq!(report.headroom, Some(5_000));
+        assert!(report.below_margin);
+        assert_eq!(report.instructions[0].units_consumed, None);
+        assert!(report
+            .reason()
+            .unwrap()
+            .starts_with("Compute headroom below 10%: 95000 of 100000"));
+    }
+
+    #[test]
+    fn default_limit_without_request() {
+        let tx = decoded(&[Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![],
+        )]);
+        let report = ComputeReport::new(&tx, None, &[], DEFAULT_COMPUTE_MARGIN_PERCENT);
+
+        assert_eq!(report.requested_limit, None);
+        assert_eq!(report.effective_limit, 200_000);
+        assert_eq!(report.headroom, None);
+        assert!(!report.below_margin);
+    }
+}
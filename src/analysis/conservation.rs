@@ -0,0 +1,68 @@
+use crate::analysis::engine::AccountAnalysis;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+/// Lamport totals across every snapshotted account. The runtime conserves
+/// lamports apart from the fee, so any imbalance means part of the state the
+/// transaction touched is missing from the diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LamportConservation {
+    pub before_total: u128,
+    pub after_total: u128,
+    /// Fee taken from the fee payer, as found in its diff.
+    pub fee: u64,
+    /// `after_total + fee - before_total`; zero when conserved.
+    pub imbalance: i128,
+    pub balanced: bool,
+    /// Writable accounts of the transaction that were not snapshotted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unwatched: Vec<Pubkey>,
+}
+
+/// Sum lamports before and after over `accounts`, crediting the fee back.
+/// `writable` lists the transaction's writable accounts, to name the ones missing.
+pub fn check_conservation(
+    accounts: &[AccountAnalysis],
+    writable: &[Pubkey],
+) -> LamportConservation {
+    let before_total: u128 = accounts.iter().map(|a| a.before.lamports as u128).sum();
+    let after_total: u128 = accounts.iter().map(|a| a.after.lamports as u128).sum();
+    let fee: u64 = accounts.iter().filter_map(|a| a.diff.fee_deducted).sum();
+    let imbalance = after_total as i128 + fee as i128 - before_total as i128;
+
+    let unwatched = writable
+        .iter()
+        .filter(|pubkey| !accounts.iter().any(|a| a.before.pubkey == **pubkey))
+        .copied()
+        .collect();
+
+    LamportConservation {
+        before_total,
+        after_total,
+        fee,
+        imbalance,
+        balanced: imbalance == 0,
+        unwatched,
+    }
+}
+
+impl LamportConservation {
+    /// Explanation for an imbalance, used as a classification reason.
+    pub fn reason(&self) -> Option<String> {
+        if self.balanced {
+            return None;
+        }
+
+        let mut reason = format!(
+            "Lamports not conserved: {} before, {} after plus {} fee ({:+}); the diff is incomplete",
+            self.before_total, self.after_total, self.fee, self.imbalance
+        );
+        if !self.unwatched.is_empty() {
+            reason.push_str(&format!(
+                ", {} writable account(s) not snapshotted",
+                self.unwatched.len()
+            ));
+        }
+        Some(reason)
+    }
+}
@@ -0,0 +1,186 @@
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+/// One program invocation reconstructed from the runtime's log lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InvocationNode {
+    pub program_id: Pubkey,
+    /// Invocation depth as logged by the runtime: 1 for top-level instructions.
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compute_units_consumed: Option<u64>,
+    /// Units the invocation could still use when it started.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compute_units_available: Option<u64>,
+    pub outcome: InvocationOutcome,
+    /// `Program log:`, `Program data:` and `Program return:` lines emitted by this invocation.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<InvocationNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvocationOutcome {
+    Success,
+    Failed(String),
+    /// No `success`/`failed` line was logged, e.g. the logs were truncated.
+    Incomplete,
+}
+
+impl InvocationNode {
+    pub(crate) fn new(program_id: Pubkey, depth: usize) -> Self {
+        Self {
+            program_id,
+            depth,
+            compute_units_consumed: None,
+            compute_units_available: None,
+            outcome: InvocationOutcome::Incomplete,
+            logs: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Rebuild the invocation tree from simulation logs. Returns one node per
+/// top-level instruction that logged an invocation; lines that cannot be
+/// attributed to an open invocation are dropped.
+pub fn parse_invocations(logs: &[String]) -> Vec<InvocationNode> {
+    let mut roots = Vec::new();
+    // Invocations that have started but not yet finished, innermost last
+    let mut stack: Vec<InvocationNode> = Vec::new();
+
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+
+        if ["log: ", "data: ", "return: "]
+            .iter()
+            .any(|prefix| rest.starts_with(prefix))
+        {
+            if let Some(node) = stack.last_mut() {
+                node.logs.push(line.clone());
+            }
+            continue;
+        }
+
+        let Some((program, event)) = rest.split_once(' ') else {
+            continue;
+        };
+        let Ok(program_id) = program.parse::<Pubkey>() else {
+            continue;
+        };
+
+        if let Some(depth) = event
+            .strip_prefix("invoke [")
+            .and_then(|d| d.strip_suffix(']'))
+            .and_then(|d| d.parse().ok())
+        {
+            stack.push(InvocationNode::new(program_id, depth));
+        } else if let Some(units) = event.strip_prefix("consumed ") {
+            if let Some(node) = stack.last_mut().filter(|n| n.program_id == program_id) {
+                let mut parts = units.split_whitespace();
+                node.compute_units_consumed = parts.next().and_then(|n| n.parse().ok());
+                node.compute_units_available = parts.nth(1).and_then(|n| n.parse().ok());
+            }
+        } else if event == "success" || event.starts_with("failed") {
+            let Some(mut node) = stack.pop() else {
+                continue;
+            };
+            node.outcome = match event.strip_prefix("failed: ") {
+                Some(reason) => InvocationOutcome::Failed(reason.to_string()),
+                None if event == "success" => InvocationOutcome::Success,
+                None => InvocationOutcome::Failed(event.to_string()),
+            };
+            attach(&mut stack, &mut roots, node);
+        }
+    }
+
+    // Invocations still open when the logs end never reported an outcome
+    while let Some(node) = stack.pop() {
+        attach(&mut stack, &mut roots, node);
+    }
+
+    roots
+}
+
+fn attach(stack: &mut [InvocationNode], roots: &mut Vec<InvocationNode>, node: InvocationNode) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nests_cpis_under_their_caller() {
+        let outer = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let tree = parse_invocations(&[
+            format!("Program {} invoke [1]", outer),
+            "Program log: Instruction: Deposit".into(),
+            format!("Program {} invoke [2]", token),
+            "Program log: Instruction: Transfer".into(),
+            format!("Program {} consumed 4645 of 190000 compute units", token),
+            format!("Program {} success", token),
+            format!("Program {} consumed 12000 of 200000 compute units", outer),
+            format!("Program {} success", outer),
+        ]);
+
+        assert_eq!(tree.len(), 1);
+        let root = &tree[0];
+        assert_eq!(root.program_id, outer);
+        assert_eq!(root.depth, 1);
+        assert_eq!(root.compute_units_consumed, Some(12000));
+        assert_eq!(root.compute_units_available, Some(200000));
+        assert_eq!(root.outcome, InvocationOutcome::Success);
+        assert_eq!(root.logs, vec!["Program log: Instruction: Deposit"]);
+
+        assert_eq!(root.children.len(), 1);
+        let child = &root.children[0];
+        assert_eq!(child.program_id, token);
+        assert_eq!(child.depth, 2);
+        assert_eq!(child.compute_units_consumed, Some(4645));
+        assert_eq!(child.logs, vec!["Program log: Instruction: Transfer"]);
+    }
+
+    #[test]
+    fn records_failures_and_sibling_instructions() {
+        let first = Pubkey::new_unique();
+        let second = Pubkey::new_unique();
+        let tree = parse_invocations(&[
+            format!("Program {} invoke [1]", first),
+            format!("Program {} success", first),
+            format!("Program {} invoke [1]", second),
+            format!("Program {} consumed 900 of 199850 compute units", second),
+            format!("Program {} failed: custom program error: 0x7d6", second),
+        ]);
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].outcome, InvocationOutcome::Success);
+        assert_eq!(
+            tree[1].outcome,
+            InvocationOutcome::Failed("custom program error: 0x7d6".into())
+        );
+    }
+
+    #[test]
+    fn truncated_logs_leave_invocations_incomplete() {
+        let outer = Pubkey::new_unique();
+        let inner = Pubkey::new_unique();
+        let tree = parse_invocations(&[
+            format!("Program {} invoke [1]", outer),
+            format!("Program {} invoke [2]", inner),
+            "Log truncated".into(),
+        ]);
+
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].outcome, InvocationOutcome::Incomplete);
+        assert_eq!(tree[0].children[0].outcome, InvocationOutcome::Incomplete);
+    }
+}
@@ -1,37 +1,312 @@
+use crate::analysis::authority::{detect_authority_changes, AuthorityChange};
+use crate::analysis::blockhash::BlockhashStatus;
+use crate::analysis::compute::ComputeReport;
+use crate::analysis::conservation::{check_conservation, LamportConservation};
+use crate::analysis::cpi::{parse_invocations, InvocationNode};
+use crate::analysis::expect::AssertionResult;
+use crate::analysis::fields::{diff_decoded, percent_change, FieldChange};
+use crate::analysis::idempotency::DoubleExecution;
+use crate::analysis::ignore::IgnoreRules;
+use crate::analysis::landed::LandedStatus;
+use crate::analysis::nonce::NonceAnalysis;
+use crate::analysis::policy::{Policy, PolicyMatch};
+use crate::analysis::rent::RentCheck;
+use crate::analysis::token::TokenDiff;
+use crate::decode::account::{decode_with, AccountDecoder, DecodedAccount};
+use crate::decode::token::TokenDecoder;
+use crate::models::rent::Rent;
 use crate::models::types::AccountSnapshot;
-use serde::Serialize;
+use crate::tx::error::{ErrorNames, TransactionError};
+use crate::tx::fee::FeeEstimate;
+use serde::{Serialize, Serializer};
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Serialize)]
+/// What happened to an account as a whole.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum ChangeKind {
+    #[default]
+    Unchanged,
+    Modified,
+    /// The account did not exist before and does after.
+    Created,
+    /// The account existed before and was closed: lamports drained to zero, data wiped.
+    Closed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for ChangeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "unchanged" => Ok(ChangeKind::Unchanged),
+            "modified" => Ok(ChangeKind::Modified),
+            "created" => Ok(ChangeKind::Created),
+            "closed" => Ok(ChangeKind::Closed),
+            _ => Err(format!(
+                "Unknown change '{}'. Expected one of: unchanged, modified, created, closed",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
 pub struct SnapshotDiff {
+    pub change_kind: ChangeKind,
     pub lamports_changed: bool,
     pub owner_changed: bool,
     pub executable_changed: bool,
     pub data_len_changed: bool,
     pub data_changed: bool,
+    pub rent_epoch_changed: bool,
+    /// `after - before` lamports, including any fee, when the balance moved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lamports_delta: Option<i128>,
+    /// `lamports_delta` relative to the pre-state balance, in percent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lamports_percent: Option<f64>,
+    /// Transaction fee taken from this account as fee payer. It is not counted in
+    /// `lamports_changed`, which then only covers movements beyond the fee.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_deducted: Option<u64>,
+    /// Changed byte ranges, with adjacent changed bytes coalesced into one range.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub data_ranges: Vec<ByteRange>,
+    /// Decoded field changes, when a decoder recognised the account on both sides.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub field_changes: Vec<FieldChange>,
+    /// Ignore rules that suppressed a change in this diff.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignored: Vec<String>,
 }
 
 impl SnapshotDiff {
     pub fn diff(before: &AccountSnapshot, after: &AccountSnapshot) -> Self {
-        Self {
+        let lamports_delta = (before.lamports != after.lamports)
+            .then(|| after.lamports as i128 - before.lamports as i128);
+        let mut diff = Self {
+            change_kind: ChangeKind::Unchanged,
             lamports_changed: before.lamports != after.lamports,
             owner_changed: before.owner != after.owner,
             executable_changed: before.executable != after.executable,
             data_len_changed: before.data_len != after.data_len,
             data_changed: before.data != after.data,
+            rent_epoch_changed: before.rent_epoch != after.rent_epoch,
+            lamports_delta,
+            lamports_percent: lamports_delta
+                .and_then(|delta| percent_change(before.lamports as i128, delta)),
+            fee_deducted: None,
+            data_ranges: diff_bytes(&before.data, &after.data),
+            field_changes: Vec::new(),
+            ignored: Vec::new(),
+        };
+        diff.change_kind = match (before.exists(), after.exists()) {
+            (false, true) => ChangeKind::Created,
+            (true, false) => ChangeKind::Closed,
+            _ if diff.has_changes() => ChangeKind::Modified,
+            _ => ChangeKind::Unchanged,
+        };
+        diff
+    }
+
+    /// Attribute `fee` of the fee payer's balance drop to the transaction fee.
+    /// Left alone when the balance dropped by less than the fee, e.g. because
+    /// the simulation did not charge it.
+    pub fn separate_fee(&mut self, before: &AccountSnapshot, after: &AccountSnapshot, fee: u64) {
+        let spent = before.lamports as i128 - after.lamports as i128;
+        if spent >= fee as i128 {
+            self.fee_deducted = Some(fee);
+            self.lamports_changed = spent != fee as i128;
         }
     }
+
+    /// Whether any compared property differs.
+    pub fn has_changes(&self) -> bool {
+        self.lamports_changed
+            || self.owner_changed
+            || self.executable_changed
+            || self.data_len_changed
+            || self.data_changed
+            || self.rent_epoch_changed
+    }
 }
 
-#[derive(Debug, Serialize)]
+/// A contiguous run of bytes that differs between pre- and post-state.
+/// When the data grew or shrank, the tail is reported as a range where
+/// one side is shorter than `length` (possibly empty).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ByteRange {
+    pub offset: usize,
+    pub length: usize,
+    #[serde(serialize_with = "serialize_hex")]
+    pub old: Vec<u8>,
+    #[serde(serialize_with = "serialize_hex")]
+    pub new: Vec<u8>,
+}
+
+/// Compare two byte slices and coalesce adjacent differing bytes into ranges.
+pub fn diff_bytes(old: &[u8], new: &[u8]) -> Vec<ByteRange> {
+    let len = old.len().max(new.len());
+    let differs = |i: usize| old.get(i) != new.get(i);
+
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < len {
+        if !differs(i) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < len && differs(i) {
+            i += 1;
+        }
+
+        ranges.push(ByteRange {
+            offset: start,
+            length: i - start,
+            old: slice_range(old, start, i),
+            new: slice_range(new, start, i),
+        });
+    }
+
+    ranges
+}
+
+fn slice_range(data: &[u8], start: usize, end: usize) -> Vec<u8> {
+    data.get(start.min(data.len())..end.min(data.len()))
+        .unwrap_or_default()
+        .to_vec()
+}
+
+/// Lower-case hex without separators, e.g. `[0x05, 0xff]` -> `"05ff"`.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn serialize_hex<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&to_hex(bytes))
+}
+
+/// How safe it is to resubmit a transaction, ordered from safest to least safe
+/// so that verdicts can be compared and gated on a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub enum RetrySafety {
+    /// No persistent state changes; a retry has no effect.
     Safe,
+    /// State changes, but running the transaction again leaves the same state.
+    IdempotentChange,
+    /// Resending the same signed bytes cannot execute twice: the runtime drops
+    /// duplicate signatures while the blockhash or nonce is still valid.
+    SafeViaSignatureDedup,
+    /// Safe only under conditions stated in the reasons, e.g. a retry that
+    /// fails harmlessly because the account it creates already exists.
+    ConditionallySafe,
+    /// Not enough information to decide, e.g. the simulation failed or an
+    /// account's post-state is missing.
+    Unknown,
+    /// Retrying would repeat a state change.
     Unsafe,
 }
 
+impl RetrySafety {
+    pub const ALL: [RetrySafety; 6] = [
+        RetrySafety::Safe,
+        RetrySafety::IdempotentChange,
+        RetrySafety::SafeViaSignatureDedup,
+        RetrySafety::ConditionallySafe,
+        RetrySafety::Unknown,
+        RetrySafety::Unsafe,
+    ];
+
+    /// One-line meaning of the level, shown next to the verdict in reports.
+    pub fn description(&self) -> &'static str {
+        match self {
+            RetrySafety::Safe => "No persistent state changes; retrying has no effect",
+            RetrySafety::IdempotentChange => {
+                "State changes, but a second execution leaves the same state"
+            }
+            RetrySafety::SafeViaSignatureDedup => {
+                "Resending the same signed bytes cannot execute twice"
+            }
+            RetrySafety::ConditionallySafe => "Safe to retry only under the conditions listed",
+            RetrySafety::Unknown => "Not enough information to decide",
+            RetrySafety::Unsafe => "Retrying would repeat a state change",
+        }
+    }
+}
+
+impl fmt::Display for RetrySafety {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for RetrySafety {
+    type Err = String;
+
+    /// Accepts the variant name in any case, with or without `-`/`_` separators,
+    /// e.g. `Unsafe`, `conditionally-safe`, `safe_via_signature_dedup`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_lowercase();
+
+        RetrySafety::ALL
+            .into_iter()
+            .find(|level| level.to_string().to_lowercase() == normalized)
+            .ok_or_else(|| {
+                let names: Vec<String> = RetrySafety::ALL.iter().map(|l| l.to_string()).collect();
+                format!(
+                    "Unknown safety level '{}'. Expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
 pub fn classify(diff: &SnapshotDiff) -> Classification {
+    match diff.change_kind {
+        ChangeKind::Closed => {
+            return Classification::new(
+                RetrySafety::Unsafe,
+                vec!["Account closed (lamports drained, data wiped); closure is irreversible".into()],
+            )
+            .with_change_kind(ChangeKind::Closed)
+        }
+        ChangeKind::Created => {
+            return Classification::new(
+                RetrySafety::ConditionallySafe,
+                vec![
+                    "Account created; safe to retry only if creation fails when the account already exists"
+                        .into(),
+                ],
+            )
+            .with_change_kind(ChangeKind::Created)
+        }
+        ChangeKind::Unchanged | ChangeKind::Modified => {}
+    }
+
     let mut reasons = Vec::new();
     if diff.lamports_changed {
-        reasons.push("Lamports changed".into());
+        match diff.fee_deducted {
+            Some(fee) => reasons.push(format!(
+                "Lamports changed beyond the {} lamport transaction fee",
+                fee
+            )),
+            None => reasons.push("Lamports changed".into()),
+        }
     }
     if diff.owner_changed {
         reasons.push("Owner changed".into());
@@ -42,8 +317,17 @@ pub fn classify(diff: &SnapshotDiff) -> Classification {
     if diff.data_len_changed {
         reasons.push("Account data size changed".into());
     }
+    if diff.rent_epoch_changed {
+        reasons.push("Rent epoch changed".into());
+    }
     if diff.data_changed {
-        reasons.push("Account data content changed".into());
+        if diff.field_changes.is_empty() {
+            reasons.push("Account data content changed".into());
+        } else {
+            for change in &diff.field_changes {
+                reasons.push(change.to_string());
+            }
+        }
     }
 
     let safety = if reasons.is_empty() {
@@ -52,46 +336,305 @@ pub fn classify(diff: &SnapshotDiff) -> Classification {
         RetrySafety::Unsafe
     };
 
-    Classification { safety, reasons }
+    Classification::new(safety, reasons).with_change_kind(diff.change_kind)
 }
 
 #[derive(Debug, Serialize)]
 pub struct Classification {
     pub safety: RetrySafety,
+    /// Meaning of `safety`, from [`RetrySafety::description`].
+    pub description: &'static str,
     pub reasons: Vec<String>,
+    /// Set on per-account classifications only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_kind: Option<ChangeKind>,
 }
 
+impl Classification {
+    pub fn new(safety: RetrySafety, reasons: Vec<String>) -> Self {
+        Self {
+            safety,
+            description: safety.description(),
+            reasons,
+            change_kind: None,
+        }
+    }
+
+    pub fn with_change_kind(mut self, kind: ChangeKind) -> Self {
+        self.change_kind = Some(kind);
+        self
+    }
+
+    /// Add a reason and raise the verdict to at least `safety`.
+    pub fn escalate(&mut self, safety: RetrySafety, reason: String) {
+        self.raise(safety);
+        self.reasons.push(reason);
+    }
+
+    /// Raise the verdict to at least `safety`, for findings reported on their own.
+    pub fn raise(&mut self, safety: RetrySafety) {
+        self.safety = self.safety.max(safety);
+        self.description = self.safety.description();
+    }
+}
+
+/// Diff and classification for a single snapshotted account.
 #[derive(Debug, Serialize)]
-pub struct AnalysisResult {
+pub struct AccountAnalysis {
     pub before: AccountSnapshot,
     pub after: AccountSnapshot,
     pub diff: SnapshotDiff,
     pub classification: Classification,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded_before: Option<DecodedAccount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded_after: Option<DecodedAccount>,
+    /// Token movements, for accounts owned by SPL Token or Token-2022.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<TokenDiff>,
+    /// Changes of who controls the account, from its owner and known layouts.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub authority_changes: Vec<AuthorityChange>,
+    /// Rent-exemption status of the post-state, when rent parameters are known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rent: Option<RentCheck>,
+    /// The policy rule that decided this account's verdict, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PolicyMatch>,
+    /// The simulation returned no post-state, so `after` is a copy of `before`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub post_state_missing: bool,
+}
+
+/// Everything beyond the raw snapshots that shapes an analysis.
+#[derive(Default)]
+pub struct AnalysisContext {
+    /// Account decoders, tried in order for each snapshot before the built-in ones.
+    pub decoders: Vec<Box<dyn AccountDecoder>>,
+    /// Changes to suppress before classifying each diff.
+    pub ignore: IgnoreRules,
+    /// Expected fee, so the fee payer's fee deduction is not reported as a transfer.
+    pub fee: Option<FeeEstimate>,
+    /// Writable accounts of the simulated transaction, when it could be decoded.
+    /// Enables the lamport conservation check.
+    pub writable_accounts: Option<Vec<Pubkey>>,
+    /// Cluster rent parameters, for the rent-exemption check.
+    pub rent: Option<Rent>,
+    /// Rules that override the built-in classification of each account.
+    pub policy: Policy,
+    /// Names for custom program error codes in simulation errors.
+    pub error_names: ErrorNames,
+    /// The snapshots were rebuilt from transaction metadata, so their data only
+    /// holds token balances and is left to the built-in token decoder.
+    pub recorded_state: bool,
+}
+
+impl AnalysisContext {
+    /// Decode with the user-supplied decoders, falling back to built-in program layouts.
+    fn decode(&self, snapshot: &AccountSnapshot) -> Option<DecodedAccount> {
+        if self.recorded_state {
+            return TokenDecoder.decode(snapshot);
+        }
+        decode_with(&self.decoders, snapshot).or_else(|| TokenDecoder.decode(snapshot))
+    }
+}
+
+/// What the simulation reported besides account states.
+#[derive(Debug, Clone, Default)]
+pub struct SimulationOutcome {
+    pub error: Option<TransactionError>,
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalysisResult {
+    pub accounts: Vec<AccountAnalysis>,
+    /// Aggregate verdict across every account: the transaction is only as
+    /// safe to retry as its least safe account.
+    pub classification: Classification,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation_error: Option<TransactionError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub double_execution: Option<DoubleExecution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lamport_conservation: Option<LamportConservation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub durable_nonce: Option<NonceAnalysis>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blockhash: Option<BlockhashStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub landed: Option<LandedStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compute: Option<ComputeReport>,
+    /// Results of `--expect` assertions against the diff.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<AssertionResult>,
+    /// Program invocations reconstructed from the simulation logs, one root per
+    /// top-level instruction.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub invocations: Vec<InvocationNode>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub simulation_logs: Vec<String>,
 }
 
-pub fn analyse(
+fn analyse_account(
     before: AccountSnapshot,
-    after: AccountSnapshot,
-    simulation_logs: Vec<String>,
-) -> AnalysisResult {
-    let diff = SnapshotDiff::diff(&before, &after);
+    after: Option<AccountSnapshot>,
+    ctx: &AnalysisContext,
+) -> AccountAnalysis {
+    let after = match after {
+        Some(after) => after,
+        None => return missing_post_state(before),
+    };
+
+    let decoded_before = ctx.decode(&before);
+    let decoded_after = ctx.decode(&after);
+    let token = TokenDiff::diff(&before, &after);
+
+    let mut diff = SnapshotDiff::diff(&before, &after);
+    diff.field_changes = diff_decoded(decoded_before.as_ref(), decoded_after.as_ref());
+    if let Some(fee) = ctx.fee.filter(|fee| fee.fee_payer == before.pubkey) {
+        diff.separate_fee(&before, &after, fee.total());
+    }
+    ctx.ignore.apply(&before.pubkey, &mut diff);
+
     let classification = classify(&diff);
+    let authority_changes = detect_authority_changes(&before, &after);
+    // Only accounts the transaction touched; one that was already short is not its doing
+    let rent = ctx
+        .rent
+        .filter(|_| diff.has_changes())
+        .and_then(|rent| RentCheck::new(&rent, &before, &after));
 
-    AnalysisResult {
+    let mut account = AccountAnalysis {
         before,
         after,
         diff,
         classification,
-        simulation_logs,
+        decoded_before,
+        decoded_after,
+        token,
+        authority_changes,
+        rent,
+        policy: None,
+        post_state_missing: false,
+    };
+
+    ctx.policy.apply(&mut account);
+
+    // Authority transfers are reported from the raw states, whatever the ignore rules
+    // and policy say. They are listed as findings of their own, in place of the
+    // generic reason for the same change
+    for change in &account.authority_changes {
+        account.classification.reasons.retain(|r| !change.covers(r));
+        account.classification.raise(RetrySafety::Unsafe);
+    }
+
+    // The runtime rejects a transaction that leaves an account below exemption, so such
+    // a post-state would not land as simulated
+    if let Some(reason) = account.rent.as_ref().and_then(|r| r.reason()) {
+        account
+            .classification
+            .escalate(RetrySafety::Unknown, reason);
+    }
+
+    account
+}
+
+/// Without a post-state nothing can be said about the account, so it is
+/// reported unchanged with an `Unknown` verdict.
+fn missing_post_state(before: AccountSnapshot) -> AccountAnalysis {
+    AccountAnalysis {
+        after: before.clone(),
+        before,
+        diff: SnapshotDiff::default(),
+        classification: Classification::new(
+            RetrySafety::Unknown,
+            vec!["Post-state unavailable from simulation".into()],
+        ),
+        decoded_before: None,
+        decoded_after: None,
+        token: None,
+        authority_changes: Vec::new(),
+        rent: None,
+        policy: None,
+        post_state_missing: true,
+    }
+}
+
+/// Combine per-account classifications into a single verdict: the least safe
+/// level wins. Reasons are prefixed with the account they came from.
+pub fn aggregate(accounts: &[AccountAnalysis]) -> Classification {
+    let mut reasons = Vec::new();
+    for account in accounts {
+        for reason in &account.classification.reasons {
+            reasons.push(format!("{}: {}", account.before.pubkey, reason));
+        }
+    }
+
+    let safety = accounts
+        .iter()
+        .map(|a| a.classification.safety)
+        .max()
+        .unwrap_or(RetrySafety::Safe);
+
+    Classification::new(safety, reasons)
+}
+
+/// Analyse a set of (pre-state, post-state) pairs, one per watched account.
+/// A `None` post-state means the simulation did not return the account.
+pub fn analyse(
+    snapshots: Vec<(AccountSnapshot, Option<AccountSnapshot>)>,
+    simulation: SimulationOutcome,
+    ctx: &AnalysisContext,
+) -> AnalysisResult {
+    let accounts: Vec<AccountAnalysis> = snapshots
+        .into_iter()
+        .map(|(before, after)| analyse_account(before, after, ctx))
+        .collect();
+
+    let mut classification = aggregate(&accounts);
+
+    let mut simulation_error = simulation.error;
+    if let Some(err) = &mut simulation_error {
+        err.resolve_name(&ctx.error_names);
+    }
+
+    // A failed simulation commits nothing, so its (empty) diff says nothing about a retry
+    if let Some(err) = &simulation_error {
+        classification.escalate(RetrySafety::Unknown, format!("Simulation failed: {}", err));
+    }
+
+    // Only a successful simulation has post-states to balance
+    let lamport_conservation = match (&ctx.writable_accounts, &simulation_error) {
+        (Some(writable), None) => Some(check_conservation(&accounts, writable)),
+        _ => None,
+    };
+    if let Some(reason) = lamport_conservation.as_ref().and_then(|c| c.reason()) {
+        classification.escalate(RetrySafety::Unknown, reason);
+    }
+
+    AnalysisResult {
+        accounts,
+        classification,
+        simulation_error,
+        double_execution: None,
+        lamport_conservation,
+        durable_nonce: None,
+        blockhash: None,
+        landed: None,
+        compute: None,
+        assertions: Vec::new(),
+        invocations: parse_invocations(&simulation.logs),
+        simulation_logs: simulation.logs,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use solana_sdk::pubkey::Pubkey;
+    use serde_json::json;
+    use std::collections::HashMap;
 
     fn base_snapshot() -> AccountSnapshot {
         AccountSnapshot {
@@ -199,6 +742,102 @@ mod tests {
         assert!(d.executable_changed);
         assert!(d.data_len_changed);
         assert!(d.data_changed);
+        assert!(d.rent_epoch_changed);
+    }
+
+    #[test]
+    fn diff_reports_lamport_delta() {
+        let before = base_snapshot();
+        let mut after = base_snapshot();
+        after.lamports += 50_000;
+
+        let d = SnapshotDiff::diff(&before, &after);
+        assert_eq!(d.lamports_delta, Some(50_000));
+        assert_eq!(d.lamports_percent, Some(5.0));
+
+        let unchanged = SnapshotDiff::diff(&before, &before);
+        assert_eq!(unchanged.lamports_delta, None);
+    }
+
+    #[test]
+    fn diff_detects_creation_and_closure() {
+        let missing = AccountSnapshot::empty(Pubkey::default());
+        let existing = base_snapshot();
+
+        assert_eq!(
+            SnapshotDiff::diff(&missing, &existing).change_kind,
+            ChangeKind::Created
+        );
+        assert_eq!(
+            SnapshotDiff::diff(&existing, &missing).change_kind,
+            ChangeKind::Closed
+        );
+        assert_eq!(
+            SnapshotDiff::diff(&existing, &existing).change_kind,
+            ChangeKind::Unchanged
+        );
+
+        let mut modified = base_snapshot();
+        modified.lamports += 1;
+        assert_eq!(
+            SnapshotDiff::diff(&existing, &modified).change_kind,
+            ChangeKind::Modified
+        );
+    }
+
+    #[test]
+    fn diff_reports_single_byte_range() {
+        let before = base_snapshot();
+        let mut after = base_snapshot();
+        after.data[0] = 6;
+        let d = SnapshotDiff::diff(&before, &after);
+        assert_eq!(
+            d.data_ranges,
+            vec![ByteRange {
+                offset: 0,
+                length: 1,
+                old: vec![0],
+                new: vec![6],
+            }]
+        );
+    }
+
+    #[test]
+    fn diff_coalesces_adjacent_bytes() {
+        let before = base_snapshot();
+        let mut after = base_snapshot();
+        after.data[8..16].copy_from_slice(&[1u8; 8]);
+        after.data[40] = 9;
+        let d = SnapshotDiff::diff(&before, &after);
+        assert_eq!(d.data_ranges.len(), 2);
+        assert_eq!(d.data_ranges[0].offset, 8);
+        assert_eq!(d.data_ranges[0].length, 8);
+        assert_eq!(d.data_ranges[1].offset, 40);
+        assert_eq!(d.data_ranges[1].length, 1);
+    }
+
+    #[test]
+    fn diff_bytes_reports_grown_tail() {
+        let ranges = diff_bytes(&[1, 2], &[1, 2, 3, 4]);
+        assert_eq!(
+            ranges,
+            vec![ByteRange {
+                offset: 2,
+                length: 2,
+                old: vec![],
+                new: vec![3, 4],
+            }]
+        );
+    }
+
+    #[test]
+    fn diff_bytes_reports_shrunk_tail() {
+        let ranges = diff_bytes(&[1, 2, 3], &[9]);
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].offset, 0);
+        assert_eq!(ranges[0].length, 3);
+        assert_eq!(ranges[0].old, vec![1, 2, 3]);
+        assert_eq!(ranges[0].new, vec![9]);
     }
 
     // — classify —
@@ -211,6 +850,7 @@ mod tests {
             executable_changed: false,
             data_len_changed: false,
             data_changed: false,
+            ..Default::default()
         };
         let c = classify(&diff);
         assert!(matches!(c.safety, RetrySafety::Safe));
@@ -225,6 +865,7 @@ mod tests {
             executable_changed: false,
             data_len_changed: false,
             data_changed: false,
+            ..Default::default()
         };
         let c = classify(&diff);
         assert!(matches!(c.safety, RetrySafety::Unsafe));
@@ -239,6 +880,7 @@ mod tests {
             executable_changed: false,
             data_len_changed: false,
             data_changed: false,
+            ..Default::default()
         };
         let c = classify(&diff);
         assert!(matches!(c.safety, RetrySafety::Unsafe));
@@ -253,6 +895,7 @@ mod tests {
             executable_changed: true,
             data_len_changed: false,
             data_changed: false,
+            ..Default::default()
         };
         let c = classify(&diff);
         assert!(matches!(c.safety, RetrySafety::Unsafe));
@@ -267,6 +910,7 @@ mod tests {
             executable_changed: false,
             data_len_changed: true,
             data_changed: false,
+            ..Default::default()
         };
         let c = classify(&diff);
         assert!(matches!(c.safety, RetrySafety::Unsafe));
@@ -281,6 +925,7 @@ mod tests {
             executable_changed: false,
             data_len_changed: false,
             data_changed: true,
+            ..Default::default()
         };
         let c = classify(&diff);
         assert!(matches!(c.safety, RetrySafety::Unsafe));
@@ -300,6 +945,25 @@ mod tests {
             .contains(&"Account data content changed".to_string()));
     }
 
+    #[test]
+    fn classify_closed_account_is_unsafe() {
+        let diff = SnapshotDiff::diff(&base_snapshot(), &AccountSnapshot::empty(Pubkey::default()));
+        let c = classify(&diff);
+        assert_eq!(c.safety, RetrySafety::Unsafe);
+        assert_eq!(c.change_kind, Some(ChangeKind::Closed));
+        assert_eq!(c.reasons.len(), 1);
+        assert!(c.reasons[0].starts_with("Account closed"));
+    }
+
+    #[test]
+    fn classify_created_account_is_conditionally_safe() {
+        let diff = SnapshotDiff::diff(&AccountSnapshot::empty(Pubkey::default()), &base_snapshot());
+        let c = classify(&diff);
+        assert_eq!(c.safety, RetrySafety::ConditionallySafe);
+        assert_eq!(c.change_kind, Some(ChangeKind::Created));
+        assert!(c.reasons[0].starts_with("Account created"));
+    }
+
     #[test]
     fn classify_all_changes_emits_all_reasons() {
         let diff = SnapshotDiff {
@@ -308,6 +972,7 @@ mod tests {
             executable_changed: true,
             data_len_changed: true,
             data_changed: true,
+            ..Default::default()
         };
         let c = classify(&diff);
         assert!(matches!(c.safety, RetrySafety::Unsafe));
@@ -320,4 +985,424 @@ mod tests {
             .reasons
             .contains(&"Account data content changed".to_string()));
     }
+
+    // — fee payer —
+
+    fn fee_for(payer: Pubkey) -> AnalysisContext {
+        AnalysisContext {
+            fee: Some(FeeEstimate {
+                fee_payer: payer,
+                base_fee: 5_000,
+                priority_fee: 1_000,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fee_only_deduction_is_not_a_change() {
+        let before = base_snapshot();
+        let mut after = base_snapshot();
+        after.lamports -= 6_000;
+
+        let result = analyse(
+            vec![(before.clone(), Some(after))],
+            SimulationOutcome::default(),
+            &fee_for(before.pubkey),
+        );
+
+        let diff = &result.accounts[0].diff;
+        assert_eq!(diff.fee_deducted, Some(6_000));
+        assert!(!diff.lamports_changed);
+        assert_eq!(result.classification.safety, RetrySafety::Safe);
+    }
+
+    #[test]
+    fn transfer_beyond_fee_is_unsafe() {
+        let before = base_snapshot();
+        let mut after = base_snapshot();
+        after.lamports -= 106_000;
+
+        let result = analyse(
+            vec![(before.clone(), Some(after))],
+            SimulationOutcome::default(),
+            &fee_for(before.pubkey),
+        );
+
+        let account = &result.accounts[0];
+        assert!(account.diff.lamports_changed);
+        assert_eq!(account.classification.safety, RetrySafety::Unsafe);
+        assert_eq!(
+            account.classification.reasons,
+            vec!["Lamports changed beyond the 6000 lamport transaction fee"]
+        );
+    }
+
+    #[test]
+    fn fee_is_only_separated_for_the_fee_payer() {
+        let before = base_snapshot();
+        let mut after = base_snapshot();
+        after.lamports -= 6_000;
+
+        let result = analyse(
+            vec![(before, Some(after))],
+            SimulationOutcome::default(),
+            &fee_for(Pubkey::new_unique()),
+        );
+
+        assert_eq!(result.accounts[0].diff.fee_deducted, None);
+        assert!(result.accounts[0].diff.lamports_changed);
+    }
+
+    // — lamport conservation —
+
+    #[test]
+    fn balanced_transfer_is_conserved() {
+        let payer = base_snapshot();
+        let vault = AccountSnapshot {
+            pubkey: Pubkey::new_unique(),
+            ..base_snapshot()
+        };
+        let mut payer_after = payer.clone();
+        payer_after.lamports -= 6_000 + 500;
+        let mut vault_after = vault.clone();
+        vault_after.lamports += 500;
+
+        let ctx = AnalysisContext {
+            writable_accounts: Some(vec![payer.pubkey, vault.pubkey]),
+            ..fee_for(payer.pubkey)
+        };
+        let result = analyse(
+            vec![(payer, Some(payer_after)), (vault, Some(vault_after))],
+            SimulationOutcome::default(),
+            &ctx,
+        );
+
+        let conservation = result.lamport_conservation.unwrap();
+        assert!(conservation.balanced);
+        assert_eq!(conservation.fee, 6_000);
+        assert!(conservation.unwatched.is_empty());
+    }
+
+    #[test]
+    fn imbalance_marks_diff_incomplete() {
+        let before = base_snapshot();
+        let hidden = Pubkey::new_unique();
+        let mut after = base_snapshot();
+        after.lamports -= 6_000 + 500;
+
+        // With the payer's transfer ignored, only the imbalance keeps the verdict from Safe
+        let ctx = AnalysisContext {
+            writable_accounts: Some(vec![before.pubkey, hidden]),
+            ignore: IgnoreRules {
+                rules: vec![crate::analysis::ignore::IgnoreRule::parse("lamports").unwrap()],
+            },
+            ..fee_for(before.pubkey)
+        };
+        let result = analyse(
+            vec![(before, Some(after))],
+            SimulationOutcome::default(),
+            &ctx,
+        );
+
+        let conservation = result.lamport_conservation.as_ref().unwrap();
+        assert!(!conservation.balanced);
+        assert_eq!(conservation.imbalance, -500);
+        assert_eq!(conservation.unwatched, vec![hidden]);
+        assert_eq!(result.classification.safety, RetrySafety::Unknown);
+        assert!(result
+            .classification
+            .reasons
+            .iter()
+            .any(|r| r.starts_with("Lamports not conserved")));
+    }
+
+    #[test]
+    fn no_conservation_check_without_transaction() {
+        let result = analyse(
+            vec![(base_snapshot(), Some(base_snapshot()))],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+        assert!(result.lamport_conservation.is_none());
+    }
+
+    // — rent —
+
+    #[test]
+    fn touched_account_below_exemption_is_unknown() {
+        use crate::analysis::policy::Policy;
+
+        let before = base_snapshot();
+        let mut after = base_snapshot();
+        after.data_len = 10_000;
+        after.data = vec![0; 10_000];
+
+        // The policy allows the growth, so only the rent shortfall sets the verdict
+        let ctx = AnalysisContext {
+            ignore: IgnoreRules::empty(),
+            policy: Policy::from_toml("[[rule]]\nchange = \"modified\"\nverdict = \"safe\"")
+                .unwrap(),
+            rent: Some(Rent {
+                lamports_per_byte_year: 3480,
+                exemption_threshold: 2.0,
+                burn_percent: 50,
+            }),
+            ..Default::default()
+        };
+        let result = analyse(
+            vec![(before, Some(after))],
+            SimulationOutcome::default(),
+            &ctx,
+        );
+
+        let account = &result.accounts[0];
+        assert!(!account.rent.as_ref().unwrap().rent_exempt);
+        assert_eq!(account.classification.safety, RetrySafety::Unknown);
+        assert!(account
+            .classification
+            .reasons
+            .iter()
+            .any(|r| r.starts_with("Account grew to 10000 bytes")));
+    }
+
+    // — analyse / aggregate —
+
+    #[test]
+    fn analyse_applies_ignore_rules_before_classifying() {
+        use crate::analysis::ignore::{IgnoreRule, IgnoreRules};
+
+        let before = base_snapshot();
+        let mut after = base_snapshot();
+        after.lamports -= 5000;
+        after.rent_epoch = 7;
+
+        let ctx = AnalysisContext {
+            ignore: IgnoreRules {
+                rules: vec![
+                    IgnoreRule::parse("lamports").unwrap(),
+                    IgnoreRule::parse("rent_epoch").unwrap(),
+                ],
+            },
+            ..Default::default()
+        };
+        let result = analyse(
+            vec![(before, Some(after))],
+            SimulationOutcome::default(),
+            &ctx,
+        );
+
+        assert_eq!(result.classification.safety, RetrySafety::Safe);
+        assert_eq!(
+            result.accounts[0].diff.ignored,
+            vec!["lamports", "rent_epoch"]
+        );
+    }
+
+    #[test]
+    fn analyse_reports_owner_change_once() {
+        let before = base_snapshot();
+        let after = AccountSnapshot {
+            owner: Pubkey::new_unique(),
+            ..base_snapshot()
+        };
+
+        let result = analyse(
+            vec![(before, Some(after))],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+
+        let account = &result.accounts[0];
+        assert!(account.classification.reasons.is_empty());
+        assert_eq!(account.classification.safety, RetrySafety::Unsafe);
+        assert_eq!(account.authority_changes.len(), 1);
+        assert_eq!(result.classification.safety, RetrySafety::Unsafe);
+    }
+
+    #[test]
+    fn analyse_multiple_accounts_independently() {
+        let untouched = base_snapshot();
+        let vault_before = AccountSnapshot {
+            pubkey: Pubkey::new_unique(),
+            ..base_snapshot()
+        };
+        let mut vault_after = vault_before.clone();
+        vault_after.lamports += 500;
+
+        let result = analyse(
+            vec![
+                (untouched.clone(), Some(untouched)),
+                (vault_before, Some(vault_after)),
+            ],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+
+        assert_eq!(result.accounts.len(), 2);
+        assert!(matches!(
+            result.accounts[0].classification.safety,
+            RetrySafety::Safe
+        ));
+        assert!(matches!(
+            result.accounts[1].classification.safety,
+            RetrySafety::Unsafe
+        ));
+    }
+
+    #[test]
+    fn aggregate_is_unsafe_if_any_account_is_unsafe() {
+        let a = base_snapshot();
+        let b_before = AccountSnapshot {
+            pubkey: Pubkey::new_unique(),
+            ..base_snapshot()
+        };
+        let mut b_after = b_before.clone();
+        b_after.data[3] = 7;
+
+        let result = analyse(
+            vec![(a.clone(), Some(a)), (b_before.clone(), Some(b_after))],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+
+        assert!(matches!(result.classification.safety, RetrySafety::Unsafe));
+        assert_eq!(
+            result.classification.reasons,
+            vec![format!("{}: Account data content changed", b_before.pubkey)]
+        );
+    }
+
+    #[test]
+    fn analyse_reports_decoded_fields_as_reasons() {
+        use crate::decode::idl::{account_discriminator, Idl};
+
+        let idl = Idl::from_json(&json!({
+            "accounts": [{
+                "name": "Counter",
+                "type": {"kind": "struct", "fields": [{"name": "count", "type": "u64"}]}
+            }]
+        }))
+        .unwrap();
+        let ctx = AnalysisContext {
+            decoders: vec![Box::new(idl)],
+            ..Default::default()
+        };
+
+        let mut before = base_snapshot();
+        before.data = account_discriminator("Counter").to_vec();
+        before.data.extend(5u64.to_le_bytes());
+        before.data_len = before.data.len();
+        let mut after = before.clone();
+        after.data[8] = 6;
+
+        let result = analyse(
+            vec![(before, Some(after))],
+            SimulationOutcome::default(),
+            &ctx,
+        );
+        let account = &result.accounts[0];
+
+        assert_eq!(account.diff.field_changes.len(), 1);
+        assert_eq!(
+            account.classification.reasons,
+            vec!["counter.count: 5 -> 6 (+1, +20.00%)"]
+        );
+    }
+
+    #[test]
+    fn aggregate_all_unchanged_is_safe() {
+        let a = base_snapshot();
+        let result = analyse(
+            vec![(a.clone(), Some(a))],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+        assert!(matches!(result.classification.safety, RetrySafety::Safe));
+        assert!(result.classification.reasons.is_empty());
+    }
+
+    #[test]
+    fn missing_post_state_is_unknown() {
+        let a = base_snapshot();
+        let result = analyse(
+            vec![(a, None)],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+        assert_eq!(
+            result.accounts[0].classification.safety,
+            RetrySafety::Unknown
+        );
+        assert_eq!(result.classification.safety, RetrySafety::Unknown);
+    }
+
+    #[test]
+    fn simulation_error_is_unknown_not_safe() {
+        let a = base_snapshot();
+        let result = analyse(
+            vec![(a.clone(), Some(a))],
+            SimulationOutcome {
+                error: Some(TransactionError::from_json(&json!("AccountNotFound"))),
+                logs: Vec::new(),
+            },
+            &AnalysisContext::default(),
+        );
+        assert_eq!(result.classification.safety, RetrySafety::Unknown);
+        assert_eq!(
+            result.classification.reasons,
+            vec!["Simulation failed: AccountNotFound"]
+        );
+    }
+
+    #[test]
+    fn simulation_error_names_idl_codes() {
+        let program = Pubkey::new_unique();
+        let ctx = AnalysisContext {
+            error_names: ErrorNames {
+                idls: vec![(None, HashMap::from([(6001, "AlreadyClaimed".to_string())]))],
+                instruction_programs: vec![program],
+            },
+            ..Default::default()
+        };
+        let result = analyse(
+            Vec::new(),
+            SimulationOutcome {
+                error: Some(TransactionError::from_json(
+                    &json!({"InstructionError": [0, {"Custom": 6001}]}),
+                )),
+                logs: Vec::new(),
+            },
+            &ctx,
+        );
+        assert_eq!(
+            result.simulation_error.unwrap().to_string(),
+            "Instruction 0 failed: AlreadyClaimed (6001)"
+        );
+    }
+
+    // — RetrySafety —
+
+    #[test]
+    fn safety_levels_are_ordered_by_risk() {
+        assert!(RetrySafety::Safe < RetrySafety::IdempotentChange);
+        assert!(RetrySafety::IdempotentChange < RetrySafety::SafeViaSignatureDedup);
+        assert!(RetrySafety::SafeViaSignatureDedup < RetrySafety::ConditionallySafe);
+        assert!(RetrySafety::ConditionallySafe < RetrySafety::Unknown);
+        assert!(RetrySafety::Unknown < RetrySafety::Unsafe);
+    }
+
+    #[test]
+    fn safety_parses_from_flexible_names() {
+        assert_eq!(
+            "conditionally-safe".parse::<RetrySafety>(),
+            Ok(RetrySafety::ConditionallySafe)
+        );
+        assert_eq!(
+            "safe_via_signature_dedup".parse::<RetrySafety>(),
+            Ok(RetrySafety::SafeViaSignatureDedup)
+        );
+        assert_eq!("Unsafe".parse::<RetrySafety>(), Ok(RetrySafety::Unsafe));
+        assert!("risky".parse::<RetrySafety>().is_err());
+    }
 }
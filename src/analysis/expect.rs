@@ -0,0 +1,470 @@
+use crate::analysis::engine::{AccountAnalysis, AnalysisResult, ChangeKind};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+
+/// What a transaction is supposed to do, read from an `--expect` TOML file.
+///
+/// ```toml
+/// [[account]]
+/// pubkey = "<VAULT>"
+/// lamports = -1_000_000
+///
+/// [[account]]
+/// pubkey = "<COUNTER>"
+/// fields = { "counter.count" = 1 }
+/// ```
+#[derive(Debug, Default)]
+pub struct Expectations {
+    /// Tolerate changes that are not expected, including changes to unlisted accounts.
+    /// Off by default, so anything unlisted fails.
+    pub allow_other_changes: bool,
+    pub accounts: Vec<ExpectedAccount>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpectedAccount {
+    pub pubkey: Pubkey,
+    pub change: Option<ChangeKind>,
+    /// Lamport delta, excluding the transaction fee.
+    pub lamports: Option<i128>,
+    /// Numeric delta per decoded field path.
+    pub fields: BTreeMap<String, i128>,
+}
+
+/// Outcome of checking one expectation against the simulated diff.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssertionResult {
+    /// `None` for checks on the whole transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<Pubkey>,
+    /// What was checked: `lamports`, a field path, `change`, `simulation`, or an
+    /// unexpected change.
+    pub target: String,
+    pub expected: String,
+    pub actual: String,
+    pub passed: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectFile {
+    #[serde(default)]
+    allow_other_changes: bool,
+    #[serde(default, rename = "account")]
+    accounts: Vec<RawAccount>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAccount {
+    pubkey: String,
+    change: Option<String>,
+    lamports: Option<i64>,
+    #[serde(default)]
+    fields: BTreeMap<String, i64>,
+}
+
+impl Expectations {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read expectations {}: {}", path, e))?;
+        Self::from_toml(&contents)
+            .map_err(|e| anyhow!("Failed to parse expectations {}: {}", path, e))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let file: ExpectFile = toml::from_str(contents)?;
+        let accounts = file
+            .accounts
+            .into_iter()
+            .map(|raw| {
+                Ok(ExpectedAccount {
+                    pubkey: raw
+                        .pubkey
+                        .parse()
+                        .map_err(|_| anyhow!("invalid pubkey '{}'", raw.pubkey))?,
+                    change: raw.change.as_deref().map(parse_change).transpose()?,
+                    lamports: raw.lamports.map(i128::from),
+                    fields: raw
+                        .fields
+                        .into_iter()
+                        .map(|(path, delta)| (path, i128::from(delta)))
+                        .collect(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            allow_other_changes: file.allow_other_changes,
+            accounts,
+        })
+    }
+
+    /// Check every expectation against `result`.
+    pub fn check(&self, result: &AnalysisResult) -> Vec<AssertionResult> {
+        let mut out = Vec::new();
+
+        // A failed simulation commits nothing, so an empty diff must not pass as expected
+        if let Some(err) = &result.simulation_error {
+            out.push(AssertionResult {
+                pubkey: None,
+                target: "simulation".into(),
+                expected: "success".into(),
+                actual: err.to_string(),
+                passed: false,
+            });
+        }
+
+        for expected in &self.accounts {
+            match result
+                .accounts
+                .iter()
+                .find(|a| a.before.pubkey == expected.pubkey)
+            {
+                // Deltas against a copied pre-state would pass without observing anything
+                Some(account) if account.post_state_missing => out.push(assertion(
+                    expected.pubkey,
+                    "post-state",
+                    "observed",
+                    "unavailable from simulation".into(),
+                    false,
+                )),
+                Some(account) => self.check_account(expected, account, &mut out),
+                None => out.push(assertion(
+                    expected.pubkey,
+                    "account",
+                    "snapshotted",
+                    "not watched".into(),
+                    false,
+                )),
+            }
+        }
+
+        if !self.allow_other_changes {
+            for account in &result.accounts {
+                let listed = self
+                    .accounts
+                    .iter()
+                    .any(|e| e.pubkey == account.before.pubkey);
+                if !listed {
+                    out.push(assertion(
+                        account.before.pubkey,
+                        "unchanged",
+                        "no changes",
+                        account.classification.reasons.join("; "),
+                        !account.post_state_missing && !account.diff.has_changes(),
+                    ));
+                }
+            }
+        }
+
+        out
+    }
+
+    fn check_account(
+        &self,
+        expected: &ExpectedAccount,
+        account: &AccountAnalysis,
+        out: &mut Vec<AssertionResult>,
+    ) {
+        let pubkey = expected.pubkey;
+        let diff = &account.diff;
+
+        if let Some(change) = expected.change {
+            out.push(assertion(
+                pubkey,
+                "change",
+                &change.to_string(),
+                diff.change_kind.to_string(),
+                diff.change_kind == change,
+            ));
+        }
+
+        let lamports = lamport_delta(account);
+        match expected.lamports {
+            Some(delta) => out.push(assertion(
+                pubkey,
+                "lamports",
+                &format!("{:+}", delta),
+                format!("{:+}", lamports),
+                lamports == delta,
+            )),
+            None if !self.allow_other_changes && lamports != 0 => out.push(assertion(
+                pubkey,
+                "lamports",
+                "+0",
+                format!("{:+}", lamports),
+                false,
+            )),
+            None => {}
+        }
+
+        for (path, delta) in &expected.fields {
+            let change = diff.field_changes.iter().find(|c| &c.path == path);
+            let (actual, passed) = match change {
+                None => ("+0".to_string(), *delta == 0),
+                Some(c) => match c.delta {
+                    Some(actual) => (format!("{:+}", actual), actual == *delta),
+                    None => (format!("{} -> {}", c.before, c.after), false),
+                },
+            };
+            out.push(assertion(
+                pubkey,
+                path,
+                &format!("{:+}", delta),
+                actual,
+                passed,
+            ));
+        }
+
+        if self.allow_other_changes {
+            return;
+        }
+
+        for change in &diff.field_changes {
+            if !expected.fields.contains_key(&change.path) {
+                out.push(assertion(
+                    pubkey,
+                    &change.path,
+                    "unchanged",
+                    format!("{} -> {}", change.before, change.after),
+                    false,
+                ));
+            }
+        }
+
+        let unexpected = [
+            (diff.owner_changed, "owner"),
+            (diff.executable_changed, "executable"),
+            (
+                diff.data_len_changed && expected.change.is_none(),
+                "data_len",
+            ),
+            (diff.rent_epoch_changed, "rent_epoch"),
+            // Data that changed without decoding into fields cannot be matched
+            (
+                diff.data_changed && diff.field_changes.is_empty() && expected.change.is_none(),
+                "data",
+            ),
+        ];
+        for (_, target) in unexpected.iter().filter(|(changed, _)| *changed) {
+            out.push(assertion(
+                pubkey,
+                target,
+                "unchanged",
+                "changed".into(),
+                false,
+            ));
+        }
+    }
+}
+
+/// Lamport change of the account, not counting the fee it paid.
+fn lamport_delta(account: &AccountAnalysis) -> i128 {
+    account.after.lamports as i128 - account.before.lamports as i128
+        + account.diff.fee_deducted.unwrap_or(0) as i128
+}
+
+fn assertion(
+    pubkey: Pubkey,
+    target: &str,
+    expected: &str,
+    actual: String,
+    passed: bool,
+) -> AssertionResult {
+    AssertionResult {
+        pubkey: Some(pubkey),
+        target: target.to_string(),
+        expected: expected.to_string(),
+        actual,
+        passed,
+    }
+}
+
+fn parse_change(value: &str) -> Result<ChangeKind> {
+    value.parse().map_err(|e: String| anyhow!(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::engine::{analyse, AnalysisContext, SimulationOutcome};
+    use crate::decode::schema::LayoutFile;
+    use crate::models::types::AccountSnapshot;
+    use crate::tx::error::TransactionError;
+
+    fn snapshot(pubkey: Pubkey, lamports: u64, count: u64) -> AccountSnapshot {
+        AccountSnapshot {
+            pubkey,
+            lamports,
+            owner: Pubkey::default(),
+            executable: false,
+            data_len: 8,
+            data: count.to_le_bytes().to_vec(),
+            rent_epoch: 0,
+        }
+    }
+
+    fn deposit(vault: Pubkey, counter: Pubkey, bystander: Pubkey) -> AnalysisResult {
+        let layout = LayoutFile::from_json(&serde_json::json!({
+            "accounts": [{
+                "name": "Counter",
+                "address": counter.to_string(),
+                "fields": [{ "name": "count", "type": "u64" }]
+            }]
+        }))
+        .unwrap();
+        let ctx = AnalysisContext {
+            decoders: vec![Box::new(layout)],
+            ..Default::default()
+        };
+        analyse(
+            vec![
+                (
+                    snapshot(vault, 5_000_000, 0),
+                    Some(snapshot(vault, 4_000_000, 0)),
+                ),
+                (
+                    snapshot(counter, 1_000_000, 5),
+                    Some(snapshot(counter, 1_000_000, 6)),
+                ),
+                (
+                    snapshot(bystander, 1_000_000, 0),
+                    Some(snapshot(bystander, 1_000_000, 0)),
+                ),
+            ],
+            SimulationOutcome::default(),
+            &ctx,
+        )
+    }
+
+    fn expectations(allow: bool, vault: Pubkey, counter: Pubkey, count: i64) -> Expectations {
+        Expectations::from_toml(&format!(
+            r#"
+            allow_other_changes = {allow}
+
+            [[account]]
+            pubkey = "{vault}"
+            lamports = -1_000_000
+
+            [[account]]
+            pubkey = "{counter}"
+            fields = {{ "counter.count" = {count} }}
+            "#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn matching_diff_passes() {
+        let (vault, counter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let result = deposit(vault, counter, Pubkey::new_unique());
+
+        let assertions = expectations(false, vault, counter, 1).check(&result);
+        assert!(assertions.iter().all(|a| a.passed), "{:?}", assertions);
+        // Two listed expectations plus the unchanged bystander
+        assert_eq!(assertions.len(), 3);
+    }
+
+    #[test]
+    fn wrong_field_delta_fails() {
+        let (vault, counter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let result = deposit(vault, counter, Pubkey::new_unique());
+
+        let failed: Vec<_> = expectations(true, vault, counter, 2)
+            .check(&result)
+            .into_iter()
+            .filter(|a| !a.passed)
+            .collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].target, "counter.count");
+        assert_eq!(failed[0].expected, "+2");
+        assert_eq!(failed[0].actual, "+1");
+    }
+
+    #[test]
+    fn unlisted_changes_fail_by_default() {
+        let (vault, counter) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let result = deposit(vault, counter, Pubkey::new_unique());
+
+        let only_vault = Expectations::from_toml(&format!(
+            "[[account]]\npubkey = \"{}\"\nlamports = -1_000_000",
+            vault
+        ))
+        .unwrap();
+        let failed: Vec<_> = only_vault
+            .check(&result)
+            .into_iter()
+            .filter(|a| !a.passed)
+            .collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].pubkey, Some(counter));
+        assert_eq!(failed[0].target, "unchanged");
+    }
+
+    #[test]
+    fn unwatched_account_fails() {
+        let result = deposit(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let missing = Pubkey::new_unique();
+        let assertions = Expectations::from_toml(&format!(
+            "allow_other_changes = true\n[[account]]\npubkey = \"{}\"\nlamports = 0",
+            missing
+        ))
+        .unwrap()
+        .check(&result);
+        assert_eq!(assertions.len(), 1);
+        assert!(!assertions[0].passed);
+        assert_eq!(assertions[0].actual, "not watched");
+    }
+
+    #[test]
+    fn failed_simulation_fails() {
+        let vault = Pubkey::new_unique();
+        let result = analyse(
+            vec![(snapshot(vault, 5_000_000, 0), None)],
+            SimulationOutcome {
+                error: Some(TransactionError::from_json(
+                    &serde_json::json!({"InstructionError": [0, {"Custom": 1}]}),
+                )),
+                ..Default::default()
+            },
+            &AnalysisContext::default(),
+        );
+
+        let assertions = Expectations::from_toml(&format!(
+            "[[account]]\npubkey = \"{}\"\nlamports = 0",
+            vault
+        ))
+        .unwrap()
+        .check(&result);
+        assert_eq!(assertions[0].pubkey, None);
+        assert_eq!(assertions[0].target, "simulation");
+        assert!(!assertions[0].passed);
+    }
+
+    #[test]
+    fn missing_post_state_fails() {
+        let vault = Pubkey::new_unique();
+        let result = analyse(
+            vec![(snapshot(vault, 5_000_000, 0), None)],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+
+        let assertions = Expectations::from_toml(&format!(
+            "[[account]]\npubkey = \"{}\"\nlamports = 0\nchange = \"unchanged\"",
+            vault
+        ))
+        .unwrap()
+        .check(&result);
+        assert_eq!(assertions.len(), 1);
+        assert_eq!(assertions[0].target, "post-state");
+        assert!(!assertions[0].passed);
+    }
+}
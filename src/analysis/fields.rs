@@ -0,0 +1,234 @@
+use crate::decode::account::DecodedAccount;
+use crate::decode::borsh::Value;
+use serde::Serialize;
+use std::fmt;
+use std::ops::Range;
+
+/// A single decoded field whose value differs between pre- and post-state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// Dotted path from the account type, e.g. `counter.count` or `pool.fees[2].rate`.
+    pub path: String,
+    pub before: Value,
+    pub after: Value,
+    /// `after - before` for integer fields.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta: Option<i128>,
+    /// `delta` relative to `before`, in percent; absent when `before` is zero.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f64>,
+    /// Bytes the field occupies in either state, when the decoder knows them.
+    #[serde(skip)]
+    pub span: Option<Range<usize>>,
+}
+
+impl FieldChange {
+    pub fn new(path: String, before: Value, after: Value) -> Self {
+        let delta = numeric_delta(&before, &after);
+        let percent = delta.and_then(|d| percent_change(as_i128(&before)?, d));
+        Self {
+            path,
+            before,
+            after,
+            delta,
+            percent,
+            span: None,
+        }
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {}{}",
+            self.path,
+            self.before,
+            self.after,
+            delta_suffix(self.delta, self.percent)
+        )
+    }
+}
+
+fn as_i128(value: &Value) -> Option<i128> {
+    match value {
+        Value::Unsigned(v) => i128::try_from(*v).ok(),
+        Value::Signed(v) => Some(*v),
+        _ => None,
+    }
+}
+
+/// `after - before` when both are integers.
+pub fn numeric_delta(before: &Value, after: &Value) -> Option<i128> {
+    as_i128(after)?.checked_sub(as_i128(before)?)
+}
+
+/// Percentage change of `before` by `delta`; `None` when `before` is zero.
+pub fn percent_change(before: i128, delta: i128) -> Option<f64> {
+    (before != 0).then(|| delta as f64 / before as f64 * 100.0)
+}
+
+/// ` (+1, +20.00%)`, ` (+1)` without a percentage, or nothing without a delta.
+pub fn delta_suffix(delta: Option<i128>, percent: Option<f64>) -> String {
+    match (delta, percent) {
+        (Some(delta), Some(percent)) => format!(" ({:+}, {:+.2}%)", delta, percent),
+        (Some(delta), None) => format!(" ({:+})", delta),
+        _ => String::new(),
+    }
+}
+
+/// Field-level diff of two decoded states. Returns nothing when either side
+/// failed to decode or the two sides decoded as different account types.
+pub fn diff_decoded(
+    before: Option<&DecodedAccount>,
+    after: Option<&DecodedAccount>,
+) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    if let (Some(before), Some(after)) = (before, after) {
+        if before.type_name == after.type_name {
+            let prefix = before.path_prefix();
+            diff_values(&prefix, &before.value, &after.value, &mut changes);
+            for change in &mut changes {
+                let key = &change.path[prefix.len()..];
+                change.span = match (before.spans.get(key), after.spans.get(key)) {
+                    (Some(b), Some(a)) => Some(b.start.min(a.start)..b.end.max(a.end)),
+                    _ => None,
+                };
+            }
+        }
+    }
+    changes
+}
+
+/// Recursively compare two values, recording a change at the deepest path
+/// where the structure still lines up.
+pub fn diff_values(path: &str, before: &Value, after: &Value, out: &mut Vec<FieldChange>) {
+    if before == after {
+        return;
+    }
+
+    match (before, after) {
+        (Value::Struct(b), Value::Struct(a)) if same_names(b, a) => {
+            for ((name, bv), (_, av)) in b.iter().zip(a) {
+                diff_values(&format!("{}.{}", path, name), bv, av, out);
+            }
+        }
+        (Value::Enum(bv, b), Value::Enum(av, a)) if bv == av && same_names(b, a) => {
+            for ((name, bv), (_, av)) in b.iter().zip(a) {
+                diff_values(&format!("{}.{}", path, name), bv, av, out);
+            }
+        }
+        (Value::Array(b), Value::Array(a)) if b.len() == a.len() => {
+            for (i, (bv, av)) in b.iter().zip(a).enumerate() {
+                diff_values(&format!("{}[{}]", path, i), bv, av, out);
+            }
+        }
+        (Value::Option(Some(b)), Value::Option(Some(a))) => diff_values(path, b, a, out),
+        _ => out.push(FieldChange::new(
+            path.to_string(),
+            before.clone(),
+            after.clone(),
+        )),
+    }
+}
+
+fn same_names(a: &[(String, Value)], b: &[(String, Value)]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|((x, _), (y, _))| x == y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter(count: u128, authority_tag: u128) -> DecodedAccount {
+        DecodedAccount {
+            type_name: "Counter".into(),
+            value: Value::Struct(vec![
+                ("count".into(), Value::Unsigned(count)),
+                (
+                    "config".into(),
+                    Value::Struct(vec![("mode".into(), Value::Unsigned(authority_tag))]),
+                ),
+            ]),
+            spans: Default::default(),
+        }
+    }
+
+    #[test]
+    fn reports_leaf_paths() {
+        let changes = diff_decoded(Some(&counter(5, 1)), Some(&counter(6, 2)));
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange::new(
+                    "counter.count".into(),
+                    Value::Unsigned(5),
+                    Value::Unsigned(6)
+                ),
+                FieldChange::new(
+                    "counter.config.mode".into(),
+                    Value::Unsigned(1),
+                    Value::Unsigned(2)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn integer_changes_carry_delta_and_percent() {
+        let change = FieldChange::new(
+            "vault.balance".into(),
+            Value::Unsigned(1_000_000),
+            Value::Unsigned(750_000),
+        );
+        assert_eq!(change.delta, Some(-250_000));
+        assert_eq!(change.percent, Some(-25.0));
+        assert_eq!(
+            change.to_string(),
+            "vault.balance: 1000000 -> 750000 (-250000, -25.00%)"
+        );
+
+        let from_zero = FieldChange::new("pool.fee".into(), Value::Signed(0), Value::Signed(-3));
+        assert_eq!(from_zero.delta, Some(-3));
+        assert_eq!(from_zero.percent, None);
+        assert_eq!(from_zero.to_string(), "pool.fee: 0 -> -3 (-3)");
+
+        let flag = FieldChange::new("pool.paused".into(), Value::Bool(false), Value::Bool(true));
+        assert_eq!(flag.delta, None);
+        assert_eq!(flag.to_string(), "pool.paused: false -> true");
+    }
+
+    #[test]
+    fn indexes_array_elements() {
+        let mut out = Vec::new();
+        diff_values(
+            "pool.rates",
+            &Value::Array(vec![Value::Unsigned(1), Value::Unsigned(2)]),
+            &Value::Array(vec![Value::Unsigned(1), Value::Unsigned(3)]),
+            &mut out,
+        );
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].path, "pool.rates[1]");
+    }
+
+    #[test]
+    fn option_transition_is_a_leaf_change() {
+        let mut out = Vec::new();
+        diff_values(
+            "vault.delegate",
+            &Value::Option(None),
+            &Value::Option(Some(Box::new(Value::Unsigned(1)))),
+            &mut out,
+        );
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].path, "vault.delegate");
+    }
+
+    #[test]
+    fn different_types_produce_no_field_changes() {
+        let mut other = counter(5, 1);
+        other.type_name = "Vault".into();
+        assert!(diff_decoded(Some(&counter(5, 1)), Some(&other)).is_empty());
+        assert!(diff_decoded(None, Some(&counter(5, 1))).is_empty());
+    }
+}
@@ -0,0 +1,345 @@
+use crate::analysis::engine::{
+    analyse, AccountAnalysis, AnalysisContext, AnalysisResult, ChangeKind, Classification,
+    RetrySafety, SimulationOutcome,
+};
+use crate::models::types::AccountSnapshot;
+use crate::tx::error::TransactionError;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+/// Post-states from simulating the transaction's instructions twice in a row,
+/// in the same account order as the first run.
+pub struct SecondRun {
+    pub post_snapshots: Vec<Option<AccountSnapshot>>,
+    pub error: Option<TransactionError>,
+    /// For each instruction of the doubled transaction, the original instruction it
+    /// repeats, as returned by [`crate::tx::replay::repeat_instructions`].
+    pub repeated_from: Vec<Option<usize>>,
+}
+
+/// Result of running the transaction a second time on top of its own post-state.
+#[derive(Debug, Serialize)]
+pub struct DoubleExecution {
+    pub verdict: RetrySafety,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub second_run_error: Option<TransactionError>,
+    /// Accounts the second execution changed again: proof that a retry is harmful.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub repeated_changes: Vec<RepeatedChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepeatedChange {
+    pub pubkey: Pubkey,
+    pub changes: Vec<String>,
+}
+
+/// Compare the second execution with the first.
+///
+/// `second` is `Err` when the doubled transaction could not be simulated at all
+/// (for example it exceeds the packet size limit).
+pub fn compare_runs(
+    first: &AnalysisResult,
+    second: Result<SecondRun, String>,
+    ctx: &AnalysisContext,
+) -> DoubleExecution {
+    let first_changed = first.accounts.iter().any(|a| a.diff.has_changes());
+    let settled = if first_changed {
+        RetrySafety::IdempotentChange
+    } else {
+        RetrySafety::Safe
+    };
+
+    let second = match second {
+        Ok(second) => second,
+        Err(e) => {
+            return DoubleExecution {
+                verdict: RetrySafety::Unknown,
+                summary: format!("Second execution could not be simulated: {}", e),
+                second_run_error: None,
+                repeated_changes: Vec::new(),
+            }
+        }
+    };
+
+    if let Some(mut err) = second.error {
+        // Only a failure in the repeated instructions shows the program rejecting a replay;
+        // one in the first half or outside any instruction means the doubled run went wrong
+        let repeated = err
+            .instruction_index()
+            .and_then(|index| second.repeated_from.get(index as usize).copied().flatten());
+        let Some(original) = repeated else {
+            return DoubleExecution {
+                verdict: RetrySafety::Unknown,
+                summary: "Second execution failed outside the repeated instructions; idempotency is undetermined".into(),
+                second_run_error: Some(err),
+                repeated_changes: Vec::new(),
+            };
+        };
+        // Error names are keyed on the original instruction's program
+        if let TransactionError::InstructionError { index, .. } = &mut err {
+            *index = original as u8;
+        }
+        err.resolve_name(&ctx.error_names);
+        // Running out of compute says nothing about whether the program guards against replays
+        let (verdict, summary) = if err.is_compute_exhausted() {
+            (
+                RetrySafety::Unknown,
+                "Second execution ran out of compute units; idempotency is undetermined"
+                    .to_string(),
+            )
+        } else {
+            (
+                settled,
+                "Second execution fails cleanly, so a retry after success cannot repeat the change"
+                    .to_string(),
+            )
+        };
+        return DoubleExecution {
+            verdict,
+            summary,
+            second_run_error: Some(err),
+            repeated_changes: Vec::new(),
+        };
+    }
+
+    let pairs = first
+        .accounts
+        .iter()
+        .zip(second.post_snapshots)
+        .map(|(account, after)| (account.after.clone(), after))
+        .collect();
+    let second_result = analyse(pairs, SimulationOutcome::default(), ctx);
+
+    let repeated_changes: Vec<RepeatedChange> = second_result
+        .accounts
+        .into_iter()
+        .filter(|a| a.diff.has_changes() || a.classification.safety == RetrySafety::Unknown)
+        .map(|a| RepeatedChange {
+            pubkey: a.before.pubkey,
+            changes: a
+                .authority_changes
+                .iter()
+                .map(|c| c.to_string())
+                .chain(a.classification.reasons)
+                .collect(),
+        })
+        .collect();
+
+    if repeated_changes.is_empty() {
+        DoubleExecution {
+            verdict: settled,
+            summary: "Second execution changes nothing".into(),
+            second_run_error: None,
+            repeated_changes,
+        }
+    } else {
+        DoubleExecution {
+            verdict: RetrySafety::Unsafe,
+            summary: format!(
+                "Second execution changed {} account(s) again; a retry repeats the effect",
+                repeated_changes.len()
+            ),
+            second_run_error: None,
+            repeated_changes,
+        }
+    }
+}
+
+/// Fold a double-execution result into the overall verdict. A conclusive second
+/// run can lower an `Unsafe` that only reflects the diff itself, but never below
+/// what other findings (closures, authority changes, rent, policy, conservation,
+/// missing post-states) require; an inconclusive one only adds a reason.
+pub fn apply_double_execution(result: &mut AnalysisResult, double: DoubleExecution) {
+    let mut reasons = std::mem::take(&mut result.classification.reasons);
+    reasons.push(format!("Double execution: {}", double.summary));
+
+    let current = result.classification.safety;
+    let safety = match double.verdict {
+        RetrySafety::Unknown => current,
+        verdict if current == RetrySafety::Unsafe => verdict.max(escalation_floor(result)),
+        verdict => verdict.max(current),
+    };
+
+    result.classification = Classification::new(safety, reasons);
+    result.double_execution = Some(double);
+}
+
+/// The least safe verdict the findings other than plain data changes call for.
+pub(crate) fn escalation_floor(result: &AnalysisResult) -> RetrySafety {
+    let mut floor = result
+        .accounts
+        .iter()
+        .map(account_floor)
+        .max()
+        .unwrap_or(RetrySafety::Safe);
+    let conservation_failed = result
+        .lamport_conservation
+        .as_ref()
+        .is_some_and(|c| c.reason().is_some());
+    if result.simulation_error.is_some() || conservation_failed {
+        floor = floor.max(RetrySafety::Unknown);
+    }
+    floor
+}
+
+fn account_floor(account: &AccountAnalysis) -> RetrySafety {
+    if account.diff.change_kind == ChangeKind::Closed || !account.authority_changes.is_empty() {
+        return RetrySafety::Unsafe;
+    }
+    // Verdicts below `Unsafe` (created accounts, missing post-states) stand as they are
+    let mut floor = match account.classification.safety {
+        RetrySafety::Unsafe => RetrySafety::Safe,
+        safety => safety,
+    };
+    if account.rent.as_ref().is_some_and(|r| r.reason().is_some()) {
+        floor = floor.max(RetrySafety::Unknown);
+    }
+    if let Some(policy) = &account.policy {
+        floor = floor.max(policy.verdict);
+    }
+    floor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn counter(value: u64) -> AccountSnapshot {
+        AccountSnapshot {
+            pubkey: Pubkey::new_from_array([7; 32]),
+            lamports: 1_000_000,
+            owner: Pubkey::default(),
+            executable: false,
+            data_len: 8,
+            data: value.to_le_bytes().to_vec(),
+            rent_epoch: 0,
+        }
+    }
+
+    fn first_run(before: u64, after: u64) -> AnalysisResult {
+        analyse(
+            vec![(counter(before), Some(counter(after)))],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        )
+    }
+
+    #[test]
+    fn repeated_increment_is_unsafe() {
+        let first = first_run(5, 6);
+        let second = SecondRun {
+            post_snapshots: vec![Some(counter(7))],
+            error: None,
+            repeated_from: vec![None, Some(0)],
+        };
+
+        let double = compare_runs(&first, Ok(second), &AnalysisContext::default());
+
+        assert_eq!(double.verdict, RetrySafety::Unsafe);
+        assert_eq!(double.repeated_changes.len(), 1);
+        assert_eq!(
+            double.repeated_changes[0].changes,
+            vec!["Account data content changed"]
+        );
+    }
+
+    #[test]
+    fn no_second_change_is_idempotent() {
+        let first = first_run(0, 1);
+        let second = SecondRun {
+            post_snapshots: vec![Some(counter(1))],
+            error: None,
+            repeated_from: vec![None, Some(0)],
+        };
+
+        let mut result = first_run(0, 1);
+        let double = compare_runs(&first, Ok(second), &AnalysisContext::default());
+        assert_eq!(double.verdict, RetrySafety::IdempotentChange);
+
+        apply_double_execution(&mut result, double);
+        assert_eq!(result.classification.safety, RetrySafety::IdempotentChange);
+        assert!(result.double_execution.is_some());
+    }
+
+    #[test]
+    fn clean_failure_is_idempotent() {
+        let first = first_run(0, 1);
+        let second = SecondRun {
+            post_snapshots: vec![None],
+            error: Some(TransactionError::from_json(
+                &json!({"InstructionError": [1, {"Custom": 2000}]}),
+            )),
+            repeated_from: vec![None, Some(0)],
+        };
+
+        let double = compare_runs(&first, Ok(second), &AnalysisContext::default());
+        assert_eq!(double.verdict, RetrySafety::IdempotentChange);
+        assert_eq!(
+            double.second_run_error.unwrap().to_string(),
+            "Instruction 0 failed: ConstraintMut (2000)"
+        );
+    }
+
+    #[test]
+    fn failure_outside_repeated_instructions_is_inconclusive() {
+        let first = first_run(0, 1);
+        for error in [
+            json!({"InstructionError": [0, {"Custom": 2000}]}),
+            json!("AccountInUse"),
+        ] {
+            let second = SecondRun {
+                post_snapshots: vec![None],
+                error: Some(TransactionError::from_json(&error)),
+                repeated_from: vec![None, Some(0)],
+            };
+
+            let double = compare_runs(&first, Ok(second), &AnalysisContext::default());
+            assert_eq!(double.verdict, RetrySafety::Unknown);
+        }
+    }
+
+    #[test]
+    fn conclusive_second_run_does_not_lower_closure() {
+        let closed = AccountSnapshot {
+            lamports: 0,
+            data_len: 0,
+            data: Vec::new(),
+            ..counter(1)
+        };
+        let mut result = analyse(
+            vec![(counter(1), Some(closed))],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+        let double = DoubleExecution {
+            verdict: RetrySafety::IdempotentChange,
+            summary: "Second execution changes nothing".into(),
+            second_run_error: None,
+            repeated_changes: Vec::new(),
+        };
+
+        apply_double_execution(&mut result, double);
+        assert_eq!(result.classification.safety, RetrySafety::Unsafe);
+    }
+
+    #[test]
+    fn compute_exhaustion_is_inconclusive() {
+        let mut result = first_run(0, 1);
+        let second = SecondRun {
+            post_snapshots: vec![None],
+            error: Some(TransactionError::from_json(
+                &json!({"InstructionError": [1, "ComputationalBudgetExceeded"]}),
+            )),
+            repeated_from: vec![None, Some(0)],
+        };
+
+        let double = compare_runs(&result, Ok(second), &AnalysisContext::default());
+        assert_eq!(double.verdict, RetrySafety::Unknown);
+
+        apply_double_execution(&mut result, double);
+        assert_eq!(result.classification.safety, RetrySafety::Unsafe);
+    }
+}
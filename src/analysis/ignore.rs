@@ -0,0 +1,464 @@
+use crate::analysis::engine::{ByteRange, ChangeKind, SnapshotDiff};
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+
+/// What part of an account an ignore rule suppresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IgnoreTarget {
+    Lamports,
+    Owner,
+    Executable,
+    DataLen,
+    RentEpoch,
+    /// All account data, including decoded fields.
+    Data,
+    /// A half-open byte range of account data; `end: None` runs to the end.
+    DataRange {
+        start: usize,
+        end: Option<usize>,
+    },
+    /// A decoded field path; also matches nested paths below it.
+    Field(String),
+}
+
+/// A single ignore rule, optionally scoped to one account.
+///
+/// Syntax: `[<PUBKEY>:]<TARGET>` where target is `lamports`, `owner`, `executable`,
+/// `data_len`, `rent_epoch`, `data`, `data[START..END]`, `data[START..]`, or a decoded
+/// field path such as `counter.last_update_slot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreRule {
+    pub account: Option<Pubkey>,
+    pub target: IgnoreTarget,
+}
+
+impl fmt::Display for IgnoreTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IgnoreTarget::Lamports => write!(f, "lamports"),
+            IgnoreTarget::Owner => write!(f, "owner"),
+            IgnoreTarget::Executable => write!(f, "executable"),
+            IgnoreTarget::DataLen => write!(f, "data_len"),
+            IgnoreTarget::RentEpoch => write!(f, "rent_epoch"),
+            IgnoreTarget::Data => write!(f, "data"),
+            IgnoreTarget::DataRange { start, end: None } => write!(f, "data[{}..]", start),
+            IgnoreTarget::DataRange {
+                start,
+                end: Some(end),
+            } => write!(f, "data[{}..{}]", start, end),
+            IgnoreTarget::Field(path) => write!(f, "{}", path),
+        }
+    }
+}
+
+impl IgnoreRule {
+    pub fn parse(rule: &str) -> Result<Self> {
+        let rule = rule.trim();
+        // A pubkey prefix is base58, so the first ':' can only be the scope separator
+        let (account, target) = match rule.split_once(':') {
+            Some((account, target)) => {
+                let account = account.parse().map_err(|_| {
+                    anyhow!("Invalid account '{}' in ignore rule '{}'", account, rule)
+                })?;
+                (Some(account), target)
+            }
+            None => (None, rule),
+        };
+
+        Ok(Self {
+            account,
+            target: parse_target(target).map_err(|e| anyhow!("{} in ignore rule '{}'", e, rule))?,
+        })
+    }
+
+    fn applies_to(&self, pubkey: &Pubkey) -> bool {
+        self.account.is_none_or(|account| account == *pubkey)
+    }
+}
+
+fn parse_target(target: &str) -> Result<IgnoreTarget> {
+    Ok(match target {
+        "lamports" => IgnoreTarget::Lamports,
+        "owner" => IgnoreTarget::Owner,
+        "executable" => IgnoreTarget::Executable,
+        "data_len" => IgnoreTarget::DataLen,
+        "rent_epoch" => IgnoreTarget::RentEpoch,
+        "data" => IgnoreTarget::Data,
+        "" => return Err(anyhow!("Empty target")),
+        _ => {
+            if let Some(range) = target
+                .strip_prefix("data[")
+                .and_then(|r| r.strip_suffix(']'))
+            {
+                let (start, end) = range
+                    .split_once("..")
+                    .ok_or_else(|| anyhow!("Byte range must look like START..END"))?;
+                let start = start
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid range start '{}'", start))?;
+                let end = match end {
+                    "" => None,
+                    end => Some(
+                        end.parse()
+                            .map_err(|_| anyhow!("Invalid range end '{}'", end))?,
+                    ),
+                };
+                if end.is_some_and(|end| end <= start) {
+                    return Err(anyhow!("Empty byte range"));
+                }
+                IgnoreTarget::DataRange { start, end }
+            } else {
+                IgnoreTarget::Field(target.to_string())
+            }
+        }
+    })
+}
+
+/// The set of ignore rules applied to every diff.
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    pub rules: Vec<IgnoreRule>,
+}
+
+impl Default for IgnoreRules {
+    /// `rent_epoch` is ignored unless defaults are turned off: it moves with
+    /// runtime rent collection, not with the transaction.
+    fn default() -> Self {
+        Self {
+            rules: vec![IgnoreRule {
+                account: None,
+                target: IgnoreTarget::RentEpoch,
+            }],
+        }
+    }
+}
+
+impl IgnoreRules {
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Suppress ignored changes in `diff`, recording each rule that fired in `diff.ignored`.
+    pub fn apply(&self, pubkey: &Pubkey, diff: &mut SnapshotDiff) {
+        for rule in self.rules.iter().filter(|r| r.applies_to(pubkey)) {
+            if apply_target(&rule.target, diff) {
+                diff.ignored.push(rule.target.to_string());
+            }
+        }
+
+        if diff.change_kind == ChangeKind::Modified && !diff.has_changes() {
+            diff.change_kind = ChangeKind::Unchanged;
+        }
+    }
+}
+
+/// Returns whether the target suppressed anything.
+fn apply_target(target: &IgnoreTarget, diff: &mut SnapshotDiff) -> bool {
+    let clear = |flag: &mut bool| std::mem::replace(flag, false);
+
+    match target {
+        IgnoreTarget::Lamports => clear(&mut diff.lamports_changed),
+        IgnoreTarget::Owner => clear(&mut diff.owner_changed),
+        IgnoreTarget::Executable => clear(&mut diff.executable_changed),
+        IgnoreTarget::DataLen => clear(&mut diff.data_len_changed),
+        IgnoreTarget::RentEpoch => clear(&mut diff.rent_epoch_changed),
+        IgnoreTarget::Data => {
+            diff.data_ranges.clear();
+            diff.field_changes.clear();
+            clear(&mut diff.data_changed)
+        }
+        IgnoreTarget::DataRange { start, end } => {
+            let covered = |ranges: &[ByteRange]| ranges.iter().map(|r| r.length).sum::<usize>();
+            let before = covered(&diff.data_ranges);
+            let end = end.unwrap_or(usize::MAX);
+            remove_range(&mut diff.data_ranges, *start, end);
+
+            let fields_before = diff.field_changes.len();
+            diff.field_changes.retain(|change| {
+                !change
+                    .span
+                    .as_ref()
+                    .is_some_and(|span| *start <= span.start && span.end <= end)
+            });
+
+            let suppressed =
+                covered(&diff.data_ranges) != before || diff.field_changes.len() != fields_before;
+            if diff.data_ranges.is_empty() {
+                diff.field_changes.clear();
+                clear(&mut diff.data_changed) || suppressed
+            } else {
+                suppressed
+            }
+        }
+        IgnoreTarget::Field(path) => {
+            let (ignored, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut diff.field_changes)
+                .into_iter()
+                .partition(|c| path_matches(path, &c.path));
+            diff.field_changes = kept;
+            // The bytes behind the ignored fields go with them; any other changed
+            // bytes, such as padding the layout does not model, still count
+            for span in ignored.iter().filter_map(|c| c.span.as_ref()) {
+                remove_range(&mut diff.data_ranges, span.start, span.end);
+            }
+            if !ignored.is_empty() && diff.data_ranges.is_empty() {
+                diff.data_changed = false;
+            }
+            !ignored.is_empty()
+        }
+    }
+}
+
+/// Remove `[start, end)` from every changed range.
+fn remove_range(ranges: &mut Vec<ByteRange>, start: usize, end: usize) {
+    *ranges = std::mem::take(ranges)
+        .into_iter()
+        .flat_map(|range| subtract(range, start, end))
+        .collect();
+}
+
+/// Whether `path` is the field named by `rule` or nested below it.
+pub(crate) fn path_matches(rule: &str, path: &str) -> bool {
+    path == rule
+        || path
+            .strip_prefix(rule)
+            .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+}
+
+/// Remove `[start, end)` from a changed range, keeping whatever lies outside it.
+fn subtract(range: ByteRange, start: usize, end: usize) -> Vec<ByteRange> {
+    let range_end = range.offset + range.length;
+    if end <= range.offset || start >= range_end {
+        return vec![range];
+    }
+
+    let piece = |from: usize, to: usize| {
+        let slice = |bytes: &[u8]| {
+            let lo = (from - range.offset).min(bytes.len());
+            let hi = (to - range.offset).min(bytes.len());
+            bytes[lo..hi].to_vec()
+        };
+        ByteRange {
+            offset: from,
+            length: to - from,
+            old: slice(&range.old),
+            new: slice(&range.new),
+        }
+    };
+
+    let mut out = Vec::new();
+    if start > range.offset {
+        out.push(piece(range.offset, start));
+    }
+    if end < range_end {
+        out.push(piece(end, range_end));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::engine::diff_bytes;
+    use crate::analysis::fields::FieldChange;
+    use crate::decode::borsh::Value;
+
+    #[test]
+    fn parses_rules() {
+        let pk = Pubkey::new_unique();
+        assert_eq!(
+            IgnoreRule::parse("lamports").unwrap(),
+            IgnoreRule {
+                account: None,
+                target: IgnoreTarget::Lamports,
+            }
+        );
+        assert_eq!(
+            IgnoreRule::parse(&format!("{}:data[0..8]", pk)).unwrap(),
+            IgnoreRule {
+                account: Some(pk),
+                target: IgnoreTarget::DataRange {
+                    start: 0,
+                    end: Some(8),
+                },
+            }
+        );
+        assert_eq!(
+            IgnoreRule::parse("data[16..]").unwrap().target,
+            IgnoreTarget::DataRange {
+                start: 16,
+                end: None,
+            }
+        );
+        assert_eq!(
+            IgnoreRule::parse("oracle.last_update_slot").unwrap().target,
+            IgnoreTarget::Field("oracle.last_update_slot".into())
+        );
+        assert!(IgnoreRule::parse("data[8..4]").is_err());
+        assert!(IgnoreRule::parse("notapubkey:lamports").is_err());
+    }
+
+    #[test]
+    fn ignores_lamports_only_for_scoped_account() {
+        let payer = Pubkey::new_unique();
+        let rules = IgnoreRules {
+            rules: vec![IgnoreRule::parse(&format!("{}:lamports", payer)).unwrap()],
+        };
+
+        let mut diff = SnapshotDiff {
+            lamports_changed: true,
+            ..Default::default()
+        };
+        rules.apply(&Pubkey::new_unique(), &mut diff);
+        assert!(diff.lamports_changed);
+
+        rules.apply(&payer, &mut diff);
+        assert!(!diff.lamports_changed);
+        assert_eq!(diff.ignored, vec!["lamports"]);
+    }
+
+    #[test]
+    fn byte_range_splits_changed_ranges() {
+        let old = vec![0u8; 16];
+        let mut new = old.clone();
+        new[4..12].copy_from_slice(&[1; 8]);
+        let mut diff = SnapshotDiff {
+            data_changed: true,
+            data_ranges: diff_bytes(&old, &new),
+            ..Default::default()
+        };
+
+        let rules = IgnoreRules {
+            rules: vec![IgnoreRule::parse("data[6..10]").unwrap()],
+        };
+        rules.apply(&Pubkey::default(), &mut diff);
+
+        assert!(diff.data_changed);
+        assert_eq!(
+            diff.data_ranges
+                .iter()
+                .map(|r| (r.offset, r.length))
+                .collect::<Vec<_>>(),
+            vec![(4, 2), (10, 2)]
+        );
+    }
+
+    #[test]
+    fn byte_range_covering_every_change_clears_data_changed() {
+        let mut diff = SnapshotDiff {
+            data_changed: true,
+            data_ranges: diff_bytes(&[0; 8], &[1; 8]),
+            ..Default::default()
+        };
+        let rules = IgnoreRules {
+            rules: vec![IgnoreRule::parse("data[0..8]").unwrap()],
+        };
+        rules.apply(&Pubkey::default(), &mut diff);
+
+        assert!(!diff.data_changed);
+        assert!(!diff.has_changes());
+        assert_eq!(diff.ignored, vec!["data[0..8]"]);
+    }
+
+    #[test]
+    fn byte_range_drops_fields_inside_it() {
+        let mut old = vec![0u8; 16];
+        old[0] = 1;
+        let new = vec![0u8; 16];
+        let field = |path: &str, span| FieldChange {
+            span: Some(span),
+            ..FieldChange::new(path.into(), Value::Unsigned(0), Value::Unsigned(1))
+        };
+        let mut diff = SnapshotDiff {
+            data_changed: true,
+            data_ranges: diff_bytes(&old, &new),
+            field_changes: vec![field("oracle.flag", 0..8), field("oracle.slot", 8..16)],
+            ..Default::default()
+        };
+
+        let rules = IgnoreRules {
+            rules: vec![IgnoreRule::parse("data[8..]").unwrap()],
+        };
+        rules.apply(&Pubkey::default(), &mut diff);
+
+        assert_eq!(diff.ignored, vec!["data[8..]"]);
+        assert_eq!(diff.field_changes.len(), 1);
+        assert_eq!(diff.field_changes[0].path, "oracle.flag");
+    }
+
+    #[test]
+    fn ignoring_every_field_change_clears_data_changed() {
+        let mut diff = SnapshotDiff {
+            data_changed: true,
+            data_ranges: diff_bytes(&[0; 8], &[1; 8]),
+            field_changes: vec![FieldChange {
+                span: Some(0..8),
+                ..FieldChange::new(
+                    "oracle.last_update.slot".into(),
+                    Value::Unsigned(0),
+                    Value::Unsigned(1),
+                )
+            }],
+            ..Default::default()
+        };
+        let rules = IgnoreRules {
+            rules: vec![IgnoreRule::parse("oracle.last_update").unwrap()],
+        };
+        rules.apply(&Pubkey::default(), &mut diff);
+
+        assert!(!diff.data_changed);
+        assert!(diff.field_changes.is_empty());
+    }
+
+    #[test]
+    fn ignored_field_keeps_undecoded_byte_changes() {
+        let old = vec![0u8; 16];
+        let mut new = old.clone();
+        new[0] = 1;
+        new[15] = 1;
+        let mut diff = SnapshotDiff {
+            data_changed: true,
+            data_ranges: diff_bytes(&old, &new),
+            field_changes: vec![FieldChange {
+                span: Some(0..8),
+                ..FieldChange::new(
+                    "oracle.last_update_slot".into(),
+                    Value::Unsigned(0),
+                    Value::Unsigned(1),
+                )
+            }],
+            ..Default::default()
+        };
+        let rules = IgnoreRules {
+            rules: vec![IgnoreRule::parse("oracle.last_update_slot").unwrap()],
+        };
+        rules.apply(&Pubkey::default(), &mut diff);
+
+        assert!(diff.field_changes.is_empty());
+        assert!(diff.data_changed);
+        assert_eq!(
+            diff.data_ranges
+                .iter()
+                .map(|r| (r.offset, r.length))
+                .collect::<Vec<_>>(),
+            vec![(15, 1)]
+        );
+    }
+
+    #[test]
+    fn rent_epoch_is_ignored_by_default() {
+        let mut diff = SnapshotDiff {
+            rent_epoch_changed: true,
+            ..Default::default()
+        };
+        IgnoreRules::default().apply(&Pubkey::default(), &mut diff);
+        assert!(!diff.rent_epoch_changed);
+
+        let mut diff = SnapshotDiff {
+            rent_epoch_changed: true,
+            ..Default::default()
+        };
+        IgnoreRules::empty().apply(&Pubkey::default(), &mut diff);
+        assert!(diff.rent_epoch_changed);
+    }
+}
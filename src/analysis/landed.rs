@@ -0,0 +1,141 @@
+use crate::analysis::engine::{AnalysisResult, Classification, RetrySafety};
+use crate::rpc::client::SignatureStatus;
+use crate::tx::error::TransactionError;
+use serde::Serialize;
+
+/// Whether the transaction has already been processed by the cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LandedStatus {
+    pub signature: String,
+    pub landed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+    /// `processed`, `confirmed` or `finalized`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmation_status: Option<String>,
+    /// Error of a transaction that landed but failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<TransactionError>,
+}
+
+impl LandedStatus {
+    pub fn new(signature: String, status: Option<SignatureStatus>) -> Self {
+        match status {
+            Some(status) => Self {
+                signature,
+                landed: true,
+                slot: Some(status.slot),
+                confirmation_status: status.confirmation_status,
+                error: status.error,
+            },
+            None => Self {
+                signature,
+                landed: false,
+                slot: None,
+                confirmation_status: None,
+                error: None,
+            },
+        }
+    }
+
+    /// Whether the transaction landed and its changes were committed.
+    pub fn succeeded(&self) -> bool {
+        self.landed && self.error.is_none()
+    }
+
+    fn reason(&self) -> String {
+        let level = self.confirmation_status.as_deref().unwrap_or("processed");
+        let slot = self.slot.unwrap_or_default();
+        match (&self.error, self.landed) {
+            (_, false) => format!(
+                "Signature {} not found on the cluster: the transaction has not landed yet",
+                self.signature
+            ),
+            (None, true) => format!(
+                "Transaction already landed ({} in slot {}): do not retry",
+                level, slot
+            ),
+            (Some(err), true) => format!(
+                "Transaction already landed but failed ({} in slot {}): {}; nothing was \
+                 committed but the fee, and the same bytes cannot be sent again",
+                level, slot, err
+            ),
+        }
+    }
+}
+
+/// A transaction that already landed successfully must not be retried, whatever
+/// the diff says, so that verdict overrides every other one.
+pub fn apply_landed_status(result: &mut AnalysisResult, status: LandedStatus) {
+    let mut reasons = std::mem::take(&mut result.classification.reasons);
+    reasons.push(status.reason());
+
+    let safety = if status.succeeded() {
+        RetrySafety::Unsafe
+    } else {
+        result.classification.safety
+    };
+
+    result.classification = Classification::new(safety, reasons);
+    result.landed = Some(status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::engine::{analyse, AnalysisContext, SimulationOutcome};
+    use serde_json::json;
+
+    fn safe_result() -> AnalysisResult {
+        analyse(
+            Vec::new(),
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        )
+    }
+
+    fn landed(error: Option<&str>) -> SignatureStatus {
+        SignatureStatus {
+            slot: 42,
+            confirmation_status: Some("finalized".into()),
+            error: error.map(|name| TransactionError::from_json(&json!(name))),
+        }
+    }
+
+    #[test]
+    fn landed_transaction_must_not_be_retried() {
+        let mut result = safe_result();
+        apply_landed_status(
+            &mut result,
+            LandedStatus::new("sig".into(), Some(landed(None))),
+        );
+
+        assert_eq!(result.classification.safety, RetrySafety::Unsafe);
+        assert_eq!(
+            result.classification.reasons,
+            vec!["Transaction already landed (finalized in slot 42): do not retry"]
+        );
+    }
+
+    #[test]
+    fn failed_landing_keeps_verdict() {
+        let mut result = safe_result();
+        apply_landed_status(
+            &mut result,
+            LandedStatus::new("sig".into(), Some(landed(Some("AccountInUse")))),
+        );
+
+        assert_eq!(result.classification.safety, RetrySafety::Safe);
+        assert!(result.landed.as_ref().unwrap().landed);
+        assert!(!result.landed.as_ref().unwrap().succeeded());
+    }
+
+    #[test]
+    fn unseen_signature_has_not_landed() {
+        let mut result = safe_result();
+        apply_landed_status(&mut result, LandedStatus::new("sig".into(), None));
+
+        assert_eq!(result.classification.safety, RetrySafety::Safe);
+        assert!(result.classification.reasons[0].contains("has not landed yet"));
+    }
+}
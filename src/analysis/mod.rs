@@ -1 +1,16 @@
+pub mod authority;
+pub mod blockhash;
+pub mod compute;
+pub mod conservation;
+pub mod cpi;
 pub mod engine;
+pub mod expect;
+pub mod fields;
+pub mod idempotency;
+pub mod ignore;
+pub mod landed;
+pub mod nonce;
+pub mod policy;
+pub mod postmortem;
+pub mod rent;
+pub mod token;
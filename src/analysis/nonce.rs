@@ -0,0 +1,217 @@
+use crate::analysis::engine::{AnalysisResult, Classification, RetrySafety};
+use crate::analysis::idempotency::escalation_floor;
+use crate::tx::nonce::{DurableNonce, NonceState};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+/// Retry semantics of a durable nonce transaction.
+#[derive(Debug, Clone, Serialize)]
+pub struct NonceAnalysis {
+    pub nonce_account: Pubkey,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authority: Option<Pubkey>,
+    /// The nonce the transaction was signed with.
+    pub transaction_nonce: String,
+    /// The nonce stored on-chain before the transaction, when the account decoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stored_nonce: Option<String>,
+    /// Whether the stored nonce still matches the transaction, so it can still land.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce_valid: Option<bool>,
+    /// Whether the simulation advanced the stored nonce.
+    pub advanced: bool,
+    pub explanation: String,
+}
+
+pub fn analyse_nonce(result: &AnalysisResult, nonce: &DurableNonce) -> NonceAnalysis {
+    let account = result
+        .accounts
+        .iter()
+        .find(|a| a.before.pubkey == nonce.nonce_account);
+    let before = account.and_then(|a| NonceState::unpack(&a.before.data));
+    let after = account.and_then(|a| NonceState::unpack(&a.after.data));
+
+    let nonce_valid = before.map(|state| state.nonce == nonce.nonce);
+    let advanced = matches!((before, after), (Some(b), Some(a)) if b.nonce != a.nonce);
+
+    let explanation = match nonce_valid {
+        Some(false) => "The nonce account no longer holds this transaction's nonce: it was \
+             already consumed or advanced, so these signed bytes can never land. Re-signing \
+             with the current nonce executes the transaction again"
+            .to_string(),
+        _ => "The transaction uses a durable nonce, which is consumed on success: resubmitting \
+             the same signed bytes cannot execute twice, but re-signing with a new nonce can"
+            .to_string(),
+    };
+
+    NonceAnalysis {
+        nonce_account: nonce.nonce_account,
+        authority: nonce.authority,
+        transaction_nonce: nonce.nonce.to_string(),
+        stored_nonce: before.map(|state| state.nonce.to_string()),
+        nonce_valid,
+        advanced,
+        explanation,
+    }
+}
+
+/// State changes only make resubmission unsafe when the same bytes can execute again,
+/// which a durable nonce rules out. Only an `Unsafe` from the plain diff is lowered:
+/// authority transfers, policy rules and a double run that repeated the change still
+/// stand, since re-signing with a new nonce executes again.
+pub fn apply_durable_nonce(result: &mut AnalysisResult, nonce: NonceAnalysis) {
+    let mut reasons = std::mem::take(&mut result.classification.reasons);
+    reasons.push(format!("Durable nonce: {}", nonce.explanation));
+
+    let mut floor = escalation_floor(result);
+    if result
+        .double_execution
+        .as_ref()
+        .is_some_and(|d| d.verdict == RetrySafety::Unsafe)
+    {
+        floor = RetrySafety::Unsafe;
+    }
+    let safety = match result.classification.safety {
+        RetrySafety::Unsafe => RetrySafety::SafeViaSignatureDedup.max(floor),
+        safety => safety,
+    };
+
+    result.classification = Classification::new(safety, reasons);
+    result.durable_nonce = Some(nonce);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::engine::{analyse, AnalysisContext, SimulationOutcome};
+    use crate::models::types::AccountSnapshot;
+    use crate::tx::nonce::tests::nonce_data;
+    use crate::tx::nonce::SYSTEM_PROGRAM_ID;
+    use solana_sdk::hash::Hash;
+
+    fn nonce_account(pubkey: Pubkey, authority: Pubkey, nonce: Hash) -> AccountSnapshot {
+        let data = nonce_data(authority, nonce);
+        AccountSnapshot {
+            pubkey,
+            lamports: 1_447_680,
+            owner: SYSTEM_PROGRAM_ID,
+            executable: false,
+            data_len: data.len(),
+            data,
+            rent_epoch: 0,
+        }
+    }
+
+    fn run(stored: Hash, signed_with: Hash) -> AnalysisResult {
+        let (account, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let before = nonce_account(account, authority, stored);
+        let after = nonce_account(account, authority, Hash::new_from_array([2; 32]));
+
+        let mut result = analyse(
+            vec![(before, Some(after))],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+        let nonce = DurableNonce {
+            nonce_account: account,
+            authority: Some(authority),
+            nonce: signed_with,
+        };
+        let analysis = analyse_nonce(&result, &nonce);
+        apply_durable_nonce(&mut result, analysis);
+        result
+    }
+
+    #[test]
+    fn valid_nonce_is_safe_via_signature_dedup() {
+        let nonce = Hash::new_from_array([1; 32]);
+        let result = run(nonce, nonce);
+
+        let analysis = result.durable_nonce.as_ref().unwrap();
+        assert_eq!(analysis.nonce_valid, Some(true));
+        assert!(analysis.advanced);
+        assert_eq!(
+            result.classification.safety,
+            RetrySafety::SafeViaSignatureDedup
+        );
+        assert!(result
+            .classification
+            .reasons
+            .last()
+            .unwrap()
+            .contains("cannot execute twice"));
+    }
+
+    #[test]
+    fn consumed_nonce_is_reported() {
+        let result = run(Hash::new_from_array([1; 32]), Hash::new_from_array([3; 32]));
+
+        let analysis = result.durable_nonce.as_ref().unwrap();
+        assert_eq!(analysis.nonce_valid, Some(false));
+        assert!(analysis.explanation.contains("can never land"));
+    }
+
+    #[test]
+    fn authority_change_stays_unsafe() {
+        use crate::decode::token::tests::mint_data;
+        use crate::decode::token::TOKEN_PROGRAM_ID;
+
+        let nonce = Hash::new_from_array([1; 32]);
+        let (account, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mint = |authority| {
+            let data = mint_data(1_000, authority);
+            AccountSnapshot {
+                pubkey: Pubkey::new_unique(),
+                lamports: 1_461_600,
+                owner: TOKEN_PROGRAM_ID,
+                executable: false,
+                data_len: data.len(),
+                data,
+                rent_epoch: 0,
+            }
+        };
+        let mint_before = mint(Some(authority));
+        let mint_after = AccountSnapshot {
+            pubkey: mint_before.pubkey,
+            ..mint(Some(Pubkey::new_unique()))
+        };
+
+        let mut result = analyse(
+            vec![
+                (
+                    nonce_account(account, authority, nonce),
+                    Some(nonce_account(
+                        account,
+                        authority,
+                        Hash::new_from_array([2; 32]),
+                    )),
+                ),
+                (mint_before, Some(mint_after)),
+            ],
+            SimulationOutcome::default(),
+            &AnalysisContext::default(),
+        );
+        let analysis = analyse_nonce(
+            &result,
+            &DurableNonce {
+                nonce_account: account,
+                authority: Some(authority),
+                nonce,
+            },
+        );
+
+        apply_durable_nonce(&mut result, analysis);
+        assert_eq!(result.classification.safety, RetrySafety::Unsafe);
+    }
+
+    #[test]
+    fn unknown_verdict_is_kept() {
+        let nonce = Hash::new_from_array([1; 32]);
+        let mut result = run(nonce, nonce);
+        result.classification = Classification::new(RetrySafety::Unknown, Vec::new());
+        let analysis = result.durable_nonce.take().unwrap();
+
+        apply_durable_nonce(&mut result, analysis);
+        assert_eq!(result.classification.safety, RetrySafety::Unknown);
+    }
+}
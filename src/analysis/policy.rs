@@ -0,0 +1,299 @@
+use crate::analysis::engine::{AccountAnalysis, ChangeKind, Classification, RetrySafety};
+use crate::analysis::ignore::path_matches;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+/// Pseudo-field that matches the account's top-level lamport balance.
+const LAMPORTS_FIELD: &str = "lamports";
+
+/// Declarative classification rules read from a `--policy` TOML file.
+///
+/// ```toml
+/// [[rule]]
+/// field = "counter.count"
+/// delta = 1
+/// verdict = "unsafe"
+/// message = "double increment"
+///
+/// [[rule]]
+/// owner = "<ORACLE_PROGRAM>"
+/// verdict = "safe"
+/// message = "oracle updates are expected"
+/// ```
+#[derive(Debug, Default)]
+pub struct Policy {
+    pub rules: Vec<PolicyRule>,
+}
+
+/// One rule: every condition that is set must hold for the rule to match.
+#[derive(Debug, Clone)]
+pub struct PolicyRule {
+    pub account: Option<Pubkey>,
+    /// Matches the owner program before or after the transaction.
+    pub owner: Option<Pubkey>,
+    /// Decoded field path (or `lamports`) that must have changed.
+    pub field: Option<String>,
+    pub change: Option<ChangeKind>,
+    /// Exact numeric change of `field`.
+    pub delta: Option<i128>,
+    pub verdict: RetrySafety,
+    pub message: Option<String>,
+}
+
+/// The rule that decided an account's verdict.
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyMatch {
+    /// 1-based position of the rule in the policy file.
+    pub rule: usize,
+    pub verdict: RetrySafety,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PolicyFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RawRule>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    account: Option<String>,
+    owner: Option<String>,
+    field: Option<String>,
+    change: Option<String>,
+    delta: Option<i64>,
+    verdict: String,
+    message: Option<String>,
+}
+
+impl Policy {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read policy {}: {}", path, e))?;
+        Self::from_toml(&contents).map_err(|e| anyhow!("Failed to parse policy {}: {}", path, e))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let file: PolicyFile = toml::from_str(contents)?;
+        let rules = file
+            .rules
+            .into_iter()
+            .enumerate()
+            .map(|(i, raw)| raw.parse().map_err(|e| anyhow!("rule {}: {}", i + 1, e)))
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// The first rule matching `account`, as a 1-based index and the rule.
+    pub fn first_match(&self, account: &AccountAnalysis) -> Option<(usize, &PolicyRule)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(account))
+            .map(|(i, rule)| (i + 1, rule))
+    }
+
+    /// Replace the account's verdict with that of the first matching rule,
+    /// keeping the original reasons after the rule's own.
+    pub fn apply(&self, account: &mut AccountAnalysis) {
+        let Some((index, rule)) = self.first_match(account) else {
+            return;
+        };
+
+        let mut reasons = vec![match &rule.message {
+            Some(message) => format!("Policy rule {}: {}", index, message),
+            None => format!("Policy rule {} matched", index),
+        }];
+        reasons.append(&mut account.classification.reasons);
+
+        account.classification =
+            Classification::new(rule.verdict, reasons).with_change_kind(account.diff.change_kind);
+        account.policy = Some(PolicyMatch {
+            rule: index,
+            verdict: rule.verdict,
+            message: rule.message.clone(),
+        });
+    }
+}
+
+impl RawRule {
+    fn parse(self) -> Result<PolicyRule> {
+        let pubkey = |value: Option<String>| {
+            value
+                .map(|v| v.parse().map_err(|_| anyhow!("invalid pubkey '{}'", v)))
+                .transpose()
+        };
+
+        if self.delta.is_some() && self.field.is_none() {
+            return Err(anyhow!("'delta' requires 'field'"));
+        }
+
+        Ok(PolicyRule {
+            account: pubkey(self.account)?,
+            owner: pubkey(self.owner)?,
+            field: self.field,
+            change: self.change.as_deref().map(parse_change_kind).transpose()?,
+            delta: self.delta.map(i128::from),
+            verdict: self.verdict.parse().map_err(|e| anyhow!("{}", e))?,
+            message: self.message,
+        })
+    }
+}
+
+fn parse_change_kind(value: &str) -> Result<ChangeKind> {
+    value.parse().map_err(|e: String| anyhow!(e))
+}
+
+impl PolicyRule {
+    pub fn matches(&self, account: &AccountAnalysis) -> bool {
+        let (before, after) = (&account.before, &account.after);
+
+        if self.account.is_some_and(|a| a != before.pubkey) {
+            return false;
+        }
+        if self
+            .owner
+            .is_some_and(|o| o != before.owner && o != after.owner)
+        {
+            return false;
+        }
+        if self.change.is_some_and(|c| c != account.diff.change_kind) {
+            return false;
+        }
+
+        match &self.field {
+            // Without `field` or `change`, only accounts the transaction touched match
+            None => self.change.is_some() || account.diff.change_kind != ChangeKind::Unchanged,
+            Some(field) => self
+                .field_deltas(field, account)
+                .into_iter()
+                .any(|delta| self.delta.is_none_or(|expected| delta == Some(expected))),
+        }
+    }
+
+    /// Numeric delta of every change under `field` (`None` for non-numeric changes).
+    fn field_deltas(&self, field: &str, account: &AccountAnalysis) -> Vec<Option<i128>> {
+        if field == LAMPORTS_FIELD {
+            return if account.diff.lamports_changed {
+                vec![account.diff.lamports_delta]
+            } else {
+                Vec::new()
+            };
+        }
+
+        account
+            .diff
+            .field_changes
+            .iter()
+            .filter(|c| path_matches(field, &c.path))
+            .map(|c| c.delta)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::engine::{analyse, AnalysisContext, SimulationOutcome};
+    use crate::analysis::ignore::IgnoreRules;
+    use crate::decode::schema::LayoutFile;
+    use crate::models::types::AccountSnapshot;
+
+    const COUNTER_PROGRAM: Pubkey = Pubkey::new_from_array([7; 32]);
+
+    fn counter(pubkey: Pubkey, count: u64) -> AccountSnapshot {
+        AccountSnapshot {
+            pubkey,
+            lamports: 1_000_000,
+            owner: COUNTER_PROGRAM,
+            executable: false,
+            data_len: 8,
+            data: count.to_le_bytes().to_vec(),
+            rent_epoch: 0,
+        }
+    }
+
+    fn analyse_counter(policy: &str, before: u64, after: u64) -> AccountAnalysis {
+        let layout = LayoutFile::from_json(&serde_json::json!({
+            "accounts": [{ "name": "Counter", "fields": [{ "name": "count", "type": "u64" }] }]
+        }))
+        .unwrap();
+        let ctx = AnalysisContext {
+            decoders: vec![Box::new(layout)],
+            ignore: IgnoreRules::empty(),
+            policy: Policy::from_toml(policy).unwrap(),
+            ..Default::default()
+        };
+        let pubkey = Pubkey::new_unique();
+        analyse(
+            vec![(counter(pubkey, before), Some(counter(pubkey, after)))],
+            SimulationOutcome::default(),
+            &ctx,
+        )
+        .accounts
+        .remove(0)
+    }
+
+    const POLICY: &str = r#"
+        [[rule]]
+        field = "counter.count"
+        delta = 1
+        verdict = "unsafe"
+        message = "double increment"
+
+        [[rule]]
+        field = "counter.count"
+        verdict = "idempotent-change"
+    "#;
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let account = analyse_counter(POLICY, 5, 6);
+        let fired = account.policy.unwrap();
+        assert_eq!(fired.rule, 1);
+        assert_eq!(account.classification.safety, RetrySafety::Unsafe);
+        assert_eq!(
+            account.classification.reasons[0],
+            "Policy rule 1: double increment"
+        );
+    }
+
+    #[test]
+    fn delta_mismatch_falls_through() {
+        let account = analyse_counter(POLICY, 5, 9);
+        assert_eq!(account.policy.unwrap().rule, 2);
+        assert_eq!(account.classification.safety, RetrySafety::IdempotentChange);
+    }
+
+    #[test]
+    fn no_match_keeps_classification() {
+        let account = analyse_counter(POLICY, 5, 5);
+        assert!(account.policy.is_none());
+        assert_eq!(account.classification.safety, RetrySafety::Safe);
+    }
+
+    #[test]
+    fn unscoped_rule_skips_unchanged_accounts() {
+        let policy = format!(
+            "[[rule]]\nowner = \"{}\"\nverdict = \"unsafe\"",
+            COUNTER_PROGRAM
+        );
+        assert!(analyse_counter(&policy, 5, 5).policy.is_none());
+        assert_eq!(analyse_counter(&policy, 5, 6).policy.unwrap().rule, 1);
+
+        let unchanged = format!("{}\nchange = \"unchanged\"", policy);
+        assert_eq!(analyse_counter(&unchanged, 5, 5).policy.unwrap().rule, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(Policy::from_toml("[[rule]]\nverdict = \"maybe\"").is_err());
+        assert!(Policy::from_toml("[[rule]]\nverdict = \"safe\"\ndelta = 1").is_err());
+        assert!(Policy::from_toml("[[rule]]\nverdict = \"safe\"\nchange = \"moved\"").is_err());
+        assert!(Policy::from_toml("[[rule]]\nverdict = \"safe\"\nowner = \"nope\"").is_err());
+    }
+}
@@ -0,0 +1,375 @@
+use crate::analysis::cpi::{InvocationNode, InvocationOutcome};
+use crate::analysis::engine::{analyse, AnalysisContext, AnalysisResult, SimulationOutcome};
+use crate::decode::token::{AccountState, TokenAccount, TOKEN_PROGRAM_ID};
+use crate::models::types::AccountSnapshot;
+use crate::tx::fee::FeeEstimate;
+use crate::tx::recorded::{RecordedTransaction, TokenBalance};
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// What a diff rebuilt from transaction metadata cannot show.
+pub const RECORDED_STATE_NOTE: &str = "Reconstructed from transaction metadata, which records \
+     only lamport and token balances: other account data and owner changes are not shown";
+
+/// Analyse a landed transaction from its recorded metadata, diffing `watched`
+/// accounts through the same engine as a simulation. `owners` holds the current
+/// owner of each watched account, which the metadata does not record.
+///
+/// `ctx.recorded_state` should be set, so the rebuilt account data is only read
+/// by the token decoder.
+pub fn analyse_recorded(
+    record: &RecordedTransaction,
+    watched: &[Pubkey],
+    owners: &HashMap<Pubkey, Pubkey>,
+    ctx: &AnalysisContext,
+) -> Result<AnalysisResult> {
+    let snapshots = recorded_snapshots(record, watched, owners)?;
+    // The landed status reports the error, so the diff is classified as committed
+    let execution = SimulationOutcome {
+        error: None,
+        logs: record.logs.clone(),
+    };
+
+    let mut result = analyse(snapshots, execution, ctx);
+    if result.invocations.is_empty() {
+        result.invocations = inner_instruction_tree(record);
+    }
+    result
+        .classification
+        .reasons
+        .push(RECORDED_STATE_NOTE.to_string());
+
+    Ok(result)
+}
+
+/// Pre- and post-state of each watched account. Token accounts are rebuilt from
+/// their recorded balances; any other account only carries its lamports and the
+/// owner given in `owners`.
+pub fn recorded_snapshots(
+    record: &RecordedTransaction,
+    watched: &[Pubkey],
+    owners: &HashMap<Pubkey, Pubkey>,
+) -> Result<Vec<(AccountSnapshot, Option<AccountSnapshot>)>> {
+    let tx = &record.transaction;
+    let num_keys = tx.account_keys.len() + tx.loaded_addresses.len();
+
+    watched
+        .iter()
+        .map(|pubkey| {
+            let index = (0..num_keys)
+                .find(|&i| tx.account_key(i) == Some(*pubkey))
+                .ok_or_else(|| anyhow!("Account {} is not part of the transaction", pubkey))?;
+            let owner = owners.get(pubkey).copied().unwrap_or_default();
+            let before = snapshot(
+                *pubkey,
+                owner,
+                index,
+                &record.pre_balances,
+                &record.pre_token_balances,
+            );
+            let after = snapshot(
+                *pubkey,
+                owner,
+                index,
+                &record.post_balances,
+                &record.post_token_balances,
+            );
+            Ok((before, Some(after)))
+        })
+        .collect()
+}
+
+fn snapshot(
+    pubkey: Pubkey,
+    owner: Pubkey,
+    index: usize,
+    balances: &[u64],
+    token_balances: &[TokenBalance],
+) -> AccountSnapshot {
+    let mut snapshot = AccountSnapshot::empty(pubkey);
+    snapshot.owner = owner;
+    snapshot.lamports = balances.get(index).copied().unwrap_or_default();
+
+    if let Some(token) = token_balances.iter().find(|t| t.account_index == index) {
+        let account = TokenAccount {
+            mint: token.mint,
+            owner: token.owner.unwrap_or_default(),
+            amount: token.amount,
+            delegate: None,
+            state: AccountState::Initialized,
+            is_native: None,
+            delegated_amount: 0,
+            close_authority: None,
+        };
+        snapshot.owner = token.program_id.unwrap_or(TOKEN_PROGRAM_ID);
+        snapshot.data = account.pack();
+        snapshot.data_len = snapshot.data.len();
+    }
+
+    snapshot
+}
+
+/// The fee actually charged, split into the signature fee and the remainder.
+pub fn recorded_fee(record: &RecordedTransaction) -> Option<FeeEstimate> {
+    let estimate = FeeEstimate::from_transaction(&record.transaction)?;
+    Some(FeeEstimate {
+        priority_fee: record.fee.saturating_sub(estimate.base_fee),
+        base_fee: estimate.base_fee.min(record.fee),
+        ..estimate
+    })
+}
+
+/// Call tree from the recorded inner instructions, for when the logs are missing.
+/// Compute units are not recorded per invocation.
+fn inner_instruction_tree(record: &RecordedTransaction) -> Vec<InvocationNode> {
+    let tx = &record.transaction;
+    let failed_at = record
+        .error
+        .as_ref()
+        .and_then(|e| e.instruction_index())
+        .map(usize::from);
+
+    tx.instructions
+        .iter()
+        .enumerate()
+        // Instructions after the failing one never ran
+        .take_while(|(index, _)| failed_at.is_none_or(|failed| *index <= failed))
+        .map(|(index, ix)| {
+            let mut root = InvocationNode::new(tx.program_id(ix).unwrap_or_default(), 1);
+            let failed = failed_at == Some(index);
+            if failed {
+                root.outcome = InvocationOutcome::Failed(
+                    record
+                        .error
+                        .as_ref()
+                        .map(|e| e.to_string())
+                        .unwrap_or_default(),
+                );
+            } else {
+                root.outcome = InvocationOutcome::Success;
+            }
+
+            let mut stack = vec![root];
+            let cpis = record
+                .inner_instructions
+                .iter()
+                .filter(|inner| inner.index as usize == index)
+                .flat_map(|inner| &inner.instructions);
+            for cpi in cpis {
+                let depth = cpi.stack_height.map_or(2, |h| (h as usize).max(2));
+                while stack.len() >= depth {
+                    close(&mut stack);
+                }
+                let program_id = tx
+                    .account_key(cpi.program_id_index as usize)
+                    .unwrap_or_default();
+                let mut node = InvocationNode::new(program_id, depth);
+                // Which CPI of a failed instruction failed is not recorded
+                if !failed {
+                    node.outcome = InvocationOutcome::Success;
+                }
+                stack.push(node);
+            }
+            while stack.len() > 1 {
+                close(&mut stack);
+            }
+            stack.remove(0)
+        })
+        .collect()
+}
+
+/// Attach the innermost open invocation to its caller.
+fn close(stack: &mut Vec<InvocationNode>) {
+    if let Some(node) = stack.pop() {
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::engine::{ChangeKind, RetrySafety};
+    use crate::analysis::token::TokenDiff;
+    use crate::decode::schema::LayoutFile;
+    use crate::tx::decode::DecodedTransaction;
+    use crate::tx::error::TransactionError;
+    use crate::tx::recorded::{InnerInstruction, InnerInstructions};
+    use serde_json::json;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::Message;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+    struct Keys {
+        payer: Pubkey,
+        vault: Pubkey,
+        program: Pubkey,
+    }
+
+    fn record(keys: &Keys) -> RecordedTransaction {
+        let ix = Instruction {
+            program_id: keys.program,
+            accounts: vec![AccountMeta::new(keys.vault, false)],
+            data: vec![],
+        };
+        let tx = Transaction::new_unsigned(Message::new(&[ix], Some(&keys.payer)));
+        let transaction: DecodedTransaction = VersionedTransaction::from(tx).into();
+
+        RecordedTransaction {
+            slot: 42,
+            transaction,
+            error: None,
+            fee: 5_000,
+            // Keys: payer, vault, program
+            pre_balances: vec![1_000_000, 2_039_280, 1],
+            post_balances: vec![995_000, 2_039_280, 1],
+            pre_token_balances: vec![TokenBalance {
+                account_index: 1,
+                mint: Pubkey::new_unique(),
+                owner: Some(keys.payer),
+                program_id: Some(TOKEN_PROGRAM_ID),
+                amount: 100,
+            }],
+            post_token_balances: Vec::new(),
+            inner_instructions: Vec::new(),
+            logs: Vec::new(),
+            compute_units_consumed: Some(1_000),
+        }
+    }
+
+    fn keys() -> Keys {
+        Keys {
+            payer: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            program: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn rebuilds_token_movements_and_fee() {
+        let keys = keys();
+        let mut record = record(&keys);
+        let mut after = record.pre_token_balances[0].clone();
+        after.amount = 40;
+        record.post_token_balances.push(after);
+
+        let ctx = AnalysisContext {
+            fee: recorded_fee(&record),
+            recorded_state: true,
+            ..Default::default()
+        };
+        let result =
+            analyse_recorded(&record, &[keys.payer, keys.vault], &HashMap::new(), &ctx).unwrap();
+
+        assert_eq!(result.accounts[0].diff.fee_deducted, Some(5_000));
+        assert!(!result.accounts[0].diff.lamports_changed);
+        match result.accounts[1].token.as_ref().unwrap() {
+            TokenDiff::Account { amount_delta, .. } => assert_eq!(*amount_delta, -60),
+            other => panic!("unexpected token diff {:?}", other),
+        }
+        assert_eq!(result.classification.safety, RetrySafety::Unsafe);
+        assert!(result
+            .classification
+            .reasons
+            .contains(&RECORDED_STATE_NOTE.to_string()));
+    }
+
+    #[test]
+    fn drained_token_account_is_closed() {
+        let keys = keys();
+        let mut record = record(&keys);
+        record.post_balances[1] = 0;
+
+        let result = analyse_recorded(
+            &record,
+            &[keys.vault],
+            &HashMap::new(),
+            &AnalysisContext::default(),
+        )
+        .unwrap();
+        assert_eq!(result.accounts[0].diff.change_kind, ChangeKind::Closed);
+    }
+
+    #[test]
+    fn unknown_account_is_an_error() {
+        let keys = keys();
+        assert!(
+            recorded_snapshots(&record(&keys), &[Pubkey::new_unique()], &HashMap::new()).is_err()
+        );
+    }
+
+    #[test]
+    fn rebuilt_token_data_skips_user_layouts() {
+        let keys = keys();
+        let layout = LayoutFile::from_json(&json!({
+            "accounts": [{
+                "name": "Vault",
+                "address": keys.vault.to_string(),
+                "fields": [{ "name": "head", "type": "u64" }]
+            }]
+        }))
+        .unwrap();
+        let ctx = AnalysisContext {
+            decoders: vec![Box::new(layout)],
+            recorded_state: true,
+            ..Default::default()
+        };
+
+        let result =
+            analyse_recorded(&record(&keys), &[keys.vault], &HashMap::new(), &ctx).unwrap();
+        assert_eq!(
+            result.accounts[0]
+                .decoded_before
+                .as_ref()
+                .unwrap()
+                .type_name,
+            "TokenAccount"
+        );
+    }
+
+    #[test]
+    fn other_accounts_keep_their_current_owner() {
+        let keys = keys();
+        let owners = HashMap::from([(keys.payer, keys.program)]);
+
+        let snapshots = recorded_snapshots(&record(&keys), &[keys.payer], &owners).unwrap();
+        assert_eq!(snapshots[0].0.owner, keys.program);
+        assert_eq!(snapshots[0].1.as_ref().unwrap().owner, keys.program);
+    }
+
+    #[test]
+    fn inner_instructions_stand_in_for_missing_logs() {
+        let keys = keys();
+        let mut record = record(&keys);
+        record.error = Some(TransactionError::from_json(
+            &json!({"InstructionError": [0, {"Custom": 1}]}),
+        ));
+        record.inner_instructions = vec![InnerInstructions {
+            index: 0,
+            instructions: vec![
+                InnerInstruction {
+                    program_id_index: 2,
+                    stack_height: Some(2),
+                },
+                InnerInstruction {
+                    program_id_index: 2,
+                    stack_height: Some(3),
+                },
+            ],
+        }];
+
+        let tree = inner_instruction_tree(&record);
+
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].program_id, keys.program);
+        assert!(matches!(tree[0].outcome, InvocationOutcome::Failed(_)));
+        assert_eq!(tree[0].children.len(), 1);
+        assert_eq!(tree[0].children[0].children[0].depth, 3);
+        assert_eq!(
+            tree[0].children[0].children[0].outcome,
+            InvocationOutcome::Incomplete
+        );
+    }
+}
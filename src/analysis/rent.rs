@@ -0,0 +1,104 @@
+use crate::models::rent::Rent;
+use crate::models::types::AccountSnapshot;
+use serde::Serialize;
+
+/// Rent-exemption status of an account's post-state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RentCheck {
+    pub lamports: u64,
+    pub data_len: usize,
+    /// Lamports needed for `data_len` bytes to be rent exempt.
+    pub minimum_balance: u64,
+    pub rent_exempt: bool,
+    /// Whether the transaction grew the account's data.
+    pub grew: bool,
+}
+
+impl RentCheck {
+    /// `None` when the account does not exist after the transaction.
+    pub fn new(rent: &Rent, before: &AccountSnapshot, after: &AccountSnapshot) -> Option<Self> {
+        if !after.exists() {
+            return None;
+        }
+
+        let minimum_balance = rent.minimum_balance(after.data_len);
+        Some(Self {
+            lamports: after.lamports,
+            data_len: after.data_len,
+            minimum_balance,
+            rent_exempt: after.lamports >= minimum_balance,
+            grew: after.data_len > before.data_len,
+        })
+    }
+
+    /// Explanation when the post-state is below exemption, used as a classification reason.
+    pub fn reason(&self) -> Option<String> {
+        if self.rent_exempt {
+            return None;
+        }
+
+        let shortfall = self.minimum_balance - self.lamports;
+        Some(if self.grew {
+            format!(
+                "Account grew to {} bytes without enough lamports for rent exemption ({} short of {})",
+                self.data_len, shortfall, self.minimum_balance
+            )
+        } else {
+            format!(
+                "Left below rent exemption: {} lamports, {} required for {} bytes",
+                self.lamports, self.minimum_balance, self.data_len
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    const RENT: Rent = Rent {
+        lamports_per_byte_year: 3480,
+        exemption_threshold: 2.0,
+        burn_percent: 50,
+    };
+
+    fn snapshot(lamports: u64, data_len: usize) -> AccountSnapshot {
+        AccountSnapshot {
+            pubkey: Pubkey::default(),
+            lamports,
+            owner: Pubkey::default(),
+            executable: false,
+            data_len,
+            data: vec![0; data_len],
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn exempt_account_has_no_reason() {
+        let state = snapshot(2_039_280, 165);
+        let check = RentCheck::new(&RENT, &state, &state).unwrap();
+        assert!(check.rent_exempt);
+        assert_eq!(check.reason(), None);
+    }
+
+    #[test]
+    fn realloc_without_top_up_is_flagged() {
+        let before = snapshot(2_039_280, 165);
+        let after = snapshot(2_039_280, 1_000);
+        let check = RentCheck::new(&RENT, &before, &after).unwrap();
+
+        assert!(!check.rent_exempt);
+        assert!(check.grew);
+        assert!(check
+            .reason()
+            .unwrap()
+            .starts_with("Account grew to 1000 bytes"));
+    }
+
+    #[test]
+    fn closed_account_is_not_checked() {
+        assert!(RentCheck::new(&RENT, &snapshot(1, 0), &snapshot(0, 0)).is_none());
+    }
+}
@@ -0,0 +1,190 @@
+use crate::decode::token::{AccountState, TokenAccount, TokenState};
+use crate::models::types::AccountSnapshot;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+/// Before/after pair for a value that changed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+fn change<T: PartialEq + Clone>(before: &T, after: &T) -> Option<Change<T>> {
+    (before != after).then(|| Change {
+        before: before.clone(),
+        after: after.clone(),
+    })
+}
+
+/// Token-level movements for an SPL Token or Token-2022 account.
+/// An account that exists on only one side is treated as empty on the other.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TokenDiff {
+    Account {
+        mint: Pubkey,
+        amount_before: u64,
+        amount_after: u64,
+        amount_delta: i128,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        delegate: Option<Change<Option<Pubkey>>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        delegated_amount: Option<Change<u64>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        close_authority: Option<Change<Option<Pubkey>>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        frozen: Option<Change<bool>>,
+    },
+    Mint {
+        supply_before: u64,
+        supply_after: u64,
+        supply_delta: i128,
+    },
+}
+
+impl TokenDiff {
+    /// Compare the token state of two snapshots, or `None` if neither side is a
+    /// token account or mint (or they are different kinds).
+    pub fn diff(before: &AccountSnapshot, after: &AccountSnapshot) -> Option<Self> {
+        match (TokenState::unpack(before), TokenState::unpack(after)) {
+            (Some(TokenState::Account(b)), Some(TokenState::Account(a))) => {
+                Some(Self::account(Some(&b), Some(&a)))
+            }
+            (Some(TokenState::Account(b)), None) => Some(Self::account(Some(&b), None)),
+            (None, Some(TokenState::Account(a))) => Some(Self::account(None, Some(&a))),
+            (Some(TokenState::Mint(b)), Some(TokenState::Mint(a))) => {
+                Some(Self::mint(b.supply, a.supply))
+            }
+            (Some(TokenState::Mint(b)), None) => Some(Self::mint(b.supply, 0)),
+            (None, Some(TokenState::Mint(a))) => Some(Self::mint(0, a.supply)),
+            _ => None,
+        }
+    }
+
+    /// Whether anything token-related moved.
+    pub fn has_changes(&self) -> bool {
+        match self {
+            TokenDiff::Account {
+                amount_delta,
+                delegate,
+                delegated_amount,
+                close_authority,
+                frozen,
+                ..
+            } => {
+                *amount_delta != 0
+                    || delegate.is_some()
+                    || delegated_amount.is_some()
+                    || close_authority.is_some()
+                    || frozen.is_some()
+            }
+            TokenDiff::Mint { supply_delta, .. } => *supply_delta != 0,
+        }
+    }
+
+    fn account(before: Option<&TokenAccount>, after: Option<&TokenAccount>) -> Self {
+        let mint = after.or(before).map(|a| a.mint).unwrap_or_default();
+        let amount = |a: Option<&TokenAccount>| a.map_or(0, |a| a.amount);
+        let delegate = |a: Option<&TokenAccount>| a.and_then(|a| a.delegate);
+        let delegated = |a: Option<&TokenAccount>| a.map_or(0, |a| a.delegated_amount);
+        let close = |a: Option<&TokenAccount>| a.and_then(|a| a.close_authority);
+        let frozen = |a: Option<&TokenAccount>| a.is_some_and(|a| a.state == AccountState::Frozen);
+
+        TokenDiff::Account {
+            mint,
+            amount_before: amount(before),
+            amount_after: amount(after),
+            amount_delta: amount(after) as i128 - amount(before) as i128,
+            delegate: change(&delegate(before), &delegate(after)),
+            delegated_amount: change(&delegated(before), &delegated(after)),
+            close_authority: change(&close(before), &close(after)),
+            frozen: change(&frozen(before), &frozen(after)),
+        }
+    }
+
+    fn mint(supply_before: u64, supply_after: u64) -> Self {
+        TokenDiff::Mint {
+            supply_before,
+            supply_after,
+            supply_delta: supply_after as i128 - supply_before as i128,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::token::tests::{mint_data, token_account_data};
+    use crate::decode::token::TOKEN_PROGRAM_ID;
+
+    fn snapshot(data: Vec<u8>) -> AccountSnapshot {
+        AccountSnapshot {
+            pubkey: Pubkey::default(),
+            lamports: 2_039_280,
+            owner: TOKEN_PROGRAM_ID,
+            executable: false,
+            data_len: data.len(),
+            data,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn reports_amount_delta_and_delegate() {
+        let delegate = Pubkey::new_unique();
+        let before = snapshot(token_account_data(1_000, None, false));
+        let after = snapshot(token_account_data(750, Some(delegate), true));
+
+        let diff = TokenDiff::diff(&before, &after).unwrap();
+        let TokenDiff::Account {
+            amount_delta,
+            delegate: delegate_change,
+            frozen,
+            close_authority,
+            ..
+        } = &diff
+        else {
+            panic!("expected token account diff");
+        };
+
+        assert_eq!(*amount_delta, -250);
+        assert_eq!(
+            *delegate_change,
+            Some(Change {
+                before: None,
+                after: Some(delegate),
+            })
+        );
+        assert_eq!(
+            *frozen,
+            Some(Change {
+                before: false,
+                after: true,
+            })
+        );
+        assert!(close_authority.is_none());
+        assert!(diff.has_changes());
+    }
+
+    #[test]
+    fn reports_mint_supply_delta() {
+        let before = snapshot(mint_data(100, None));
+        let after = snapshot(mint_data(160, None));
+        let diff = TokenDiff::diff(&before, &after).unwrap();
+        assert_eq!(
+            diff,
+            TokenDiff::Mint {
+                supply_before: 100,
+                supply_after: 160,
+                supply_delta: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn unchanged_token_account_has_no_changes() {
+        let s = snapshot(token_account_data(5, None, false));
+        assert!(!TokenDiff::diff(&s, &s).unwrap().has_changes());
+    }
+}
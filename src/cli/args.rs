@@ -1,4 +1,5 @@
-use clap::Parser;
+use crate::analysis::compute::DEFAULT_COMPUTE_MARGIN_PERCENT;
+use clap::{Parser, Subcommand};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -7,19 +8,81 @@ use clap::Parser;
     about = "Solana audit and retry-safety tool"
 )]
 pub struct Cli {
-    /// Program ID to analyse
-    #[arg(long)]
-    pub program: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Account pubkey to snapshot and diff (repeat for several accounts).
+    /// When omitted, every writable account of the transaction is diffed.
+    #[arg(long = "account", visible_alias = "program", global = true)]
+    pub accounts: Vec<String>,
 
     /// Target cluster ( devnet or mainnet)
-    #[arg(long, default_value = "devnet")]
+    #[arg(long, default_value = "devnet", global = true)]
     pub cluster: String,
 
     /// Output format (Json or text)
-    #[arg(long, default_value = "text")]
+    #[arg(long, default_value = "text", global = true)]
     pub output: String,
 
     /// Base64 encoded transaction
     #[arg(long)]
     pub tx: Option<String>,
+
+    /// Simulate the transaction a second time on top of its own post-state
+    /// to check whether a retry would repeat its effect (requires --tx)
+    #[arg(long, requires = "tx")]
+    pub double_run: bool,
+
+    /// Exit with status 2 when the overall verdict is at or above this level
+    /// (safe, idempotent-change, safe-via-signature-dedup, conditionally-safe, unknown, unsafe)
+    #[arg(long, global = true)]
+    pub fail_on: Option<String>,
+
+    /// Anchor IDL JSON file used to decode account data into fields
+    #[arg(long, global = true)]
+    pub idl: Option<String>,
+
+    /// Borsh layout file used to decode account data of non-Anchor programs
+    #[arg(long, global = true)]
+    pub layout: Option<String>,
+
+    /// Ignore a change when diffing, as `[<PUBKEY>:]<TARGET>` (repeatable).
+    /// Targets: lamports, owner, executable, data_len, rent_epoch, data,
+    /// data[START..END], or a decoded field path
+    #[arg(long = "ignore", value_name = "RULE", global = true)]
+    pub ignores: Vec<String>,
+
+    /// TOML config file with an `ignore` list of rules
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// TOML policy file of `[[rule]]` entries that override the verdict per account
+    #[arg(long, global = true)]
+    pub policy: Option<String>,
+
+    /// TOML file stating the expected diff; exits with status 1 if it does not match
+    #[arg(long, global = true)]
+    pub expect: Option<String>,
+
+    /// Last block height at which `--tx` can land, as returned with its blockhash
+    /// by `getLatestBlockhash`; used to count the blocks left before expiry
+    #[arg(long, requires = "tx")]
+    pub last_valid_block_height: Option<u64>,
+
+    /// Warn when compute headroom is below this percentage of the unit limit
+    #[arg(long, default_value_t = DEFAULT_COMPUTE_MARGIN_PERCENT, global = true)]
+    pub compute_margin: u32,
+
+    /// Do not ignore rent_epoch changes by default
+    #[arg(long, global = true)]
+    pub no_default_ignores: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Analyse a transaction that already landed, from its recorded metadata
+    Tx {
+        /// Transaction signature (base58)
+        signature: String,
+    },
 }
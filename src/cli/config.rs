@@ -0,0 +1,40 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+/// Settings read from a `--config` TOML file.
+///
+/// ```toml
+/// ignore = ["lamports", "Vault1111...:data[0..8]", "oracle.last_update_slot"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Ignore rules, in the same syntax as `--ignore`.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+impl Config {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read config {}: {}", path, e))?;
+        Self::from_toml(&contents).map_err(|e| anyhow!("Failed to parse config {}: {}", path, e))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ignore_list() {
+        let config = Config::from_toml(r#"ignore = ["lamports", "data[0..8]"]"#).unwrap();
+        assert_eq!(config.ignore, vec!["lamports", "data[0..8]"]);
+        assert!(Config::from_toml("").unwrap().ignore.is_empty());
+        assert!(Config::from_toml("unknown = 1").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use crate::decode::borsh::{Spans, Value};
+use crate::models::types::AccountSnapshot;
+use serde::Serialize;
+
+/// Account data decoded into named fields.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecodedAccount {
+    pub type_name: String,
+    pub value: Value,
+    /// Byte span of each value in the account data, where the decoder knows it.
+    #[serde(skip)]
+    pub spans: Spans,
+}
+
+impl DecodedAccount {
+    /// Prefix used for field paths in diffs, e.g. `Counter` -> `counter`.
+    pub fn path_prefix(&self) -> String {
+        to_snake_case(&self.type_name)
+    }
+}
+
+/// A source of account layouts (an Anchor IDL, a layout file, a built-in program).
+pub trait AccountDecoder {
+    /// Decode the snapshot's data, or `None` if this decoder does not recognise it.
+    fn decode(&self, snapshot: &AccountSnapshot) -> Option<DecodedAccount>;
+}
+
+/// Try each decoder in order and return the first match.
+pub fn decode_with(
+    decoders: &[Box<dyn AccountDecoder>],
+    snapshot: &AccountSnapshot,
+) -> Option<DecodedAccount> {
+    decoders.iter().find_map(|d| d.decode(snapshot))
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_prefix_is_snake_case() {
+        let decoded = DecodedAccount {
+            type_name: "UserPosition".into(),
+            value: Value::Bool(true),
+            spans: Spans::new(),
+        };
+        assert_eq!(decoded.path_prefix(), "user_position");
+    }
+}
@@ -0,0 +1,456 @@
+use crate::analysis::engine::to_hex;
+use crate::decode::layout::{Field, FieldType, TypeDef, TypeRegistry};
+use anyhow::{anyhow, Result};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
+/// Nesting limit for `Defined` types, so a self-referencing layout cannot recurse forever.
+const MAX_DEPTH: usize = 32;
+
+/// A decoded account field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Unsigned(u128),
+    Signed(i128),
+    Float(f64),
+    String(String),
+    Pubkey(Pubkey),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Option(Option<Box<Value>>),
+    Struct(Vec<(String, Value)>),
+    Enum(String, Vec<(String, Value)>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Unsigned(v) => write!(f, "{}", v),
+            Value::Signed(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::String(v) => write!(f, "{:?}", v),
+            Value::Pubkey(v) => write!(f, "{}", v),
+            Value::Bytes(v) => write!(f, "0x{}", to_hex(v)),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Option(None) => write!(f, "None"),
+            Value::Option(Some(v)) => write!(f, "Some({})", v),
+            Value::Struct(fields) => write_fields(f, fields),
+            Value::Enum(variant, fields) if fields.is_empty() => write!(f, "{}", variant),
+            Value::Enum(variant, fields) => {
+                write!(f, "{} ", variant)?;
+                write_fields(f, fields)
+            }
+        }
+    }
+}
+
+fn write_fields(f: &mut fmt::Formatter<'_>, fields: &[(String, Value)]) -> fmt::Result {
+    write!(f, "{{ ")?;
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}: {}", name, value)?;
+    }
+    write!(f, " }}")
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            // JSON consumers lose precision above 2^64, so wide integers become strings
+            Value::Unsigned(v) => match u64::try_from(*v) {
+                Ok(v) => serializer.serialize_u64(v),
+                Err(_) => serializer.serialize_str(&v.to_string()),
+            },
+            Value::Signed(v) => match i64::try_from(*v) {
+                Ok(v) => serializer.serialize_i64(v),
+                Err(_) => serializer.serialize_str(&v.to_string()),
+            },
+            Value::Float(v) => serializer.serialize_f64(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Pubkey(v) => serializer.serialize_str(&v.to_string()),
+            Value::Bytes(v) => serializer.serialize_str(&to_hex(v)),
+            Value::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Value::Option(None) => serializer.serialize_none(),
+            Value::Option(Some(v)) => v.serialize(serializer),
+            Value::Struct(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (name, value) in fields {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            }
+            Value::Enum(variant, fields) if fields.is_empty() => serializer.serialize_str(variant),
+            Value::Enum(variant, fields) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(variant, &Value::Struct(fields.clone()))?;
+                map.end()
+            }
+        }
+    }
+}
+
+/// Where each decoded value lies in the account data, keyed by its path below the
+/// account value: `""` for the whole value, then e.g. `.count` or `.fees[2].rate`.
+pub type Spans = BTreeMap<String, Range<usize>>;
+
+/// Tracks the position in the account data while decoding.
+struct Cursor {
+    /// Length of the account data; `data` is always a suffix of it.
+    end: usize,
+    spans: Spans,
+}
+
+impl Cursor {
+    fn position(&self, data: &[u8]) -> usize {
+        self.end - data.len()
+    }
+}
+
+/// Decode a Borsh-encoded value of type `def`, consuming bytes from the front of `data`.
+/// Trailing bytes are left in `data`; account buffers are often over-allocated.
+pub fn decode_def(def: &TypeDef, registry: &TypeRegistry, data: &mut &[u8]) -> Result<Value> {
+    let mut cursor = Cursor {
+        end: data.len(),
+        spans: Spans::new(),
+    };
+    decode_def_at(def, registry, data, 0, "", &mut cursor)
+}
+
+/// Decode an account of type `def` whose value starts at `offset` in `account_data`,
+/// recording the byte span of every decoded value.
+pub fn decode_account(
+    def: &TypeDef,
+    registry: &TypeRegistry,
+    account_data: &[u8],
+    offset: usize,
+) -> Result<(Value, Spans)> {
+    let mut data = account_data
+        .get(offset..)
+        .ok_or_else(|| anyhow!("Account data shorter than {} bytes", offset))?;
+    let mut cursor = Cursor {
+        end: account_data.len(),
+        spans: Spans::new(),
+    };
+    let value = decode_def_at(def, registry, &mut data, 0, "", &mut cursor)?;
+    cursor
+        .spans
+        .insert(String::new(), offset..cursor.position(data));
+    Ok((value, cursor.spans))
+}
+
+/// Decode a single Borsh-encoded field of type `ty`.
+pub fn decode_type(ty: &FieldType, registry: &TypeRegistry, data: &mut &[u8]) -> Result<Value> {
+    let mut cursor = Cursor {
+        end: data.len(),
+        spans: Spans::new(),
+    };
+    decode_type_at(ty, registry, data, 0, "", &mut cursor)
+}
+
+fn decode_def_at(
+    def: &TypeDef,
+    registry: &TypeRegistry,
+    data: &mut &[u8],
+    depth: usize,
+    path: &str,
+    cursor: &mut Cursor,
+) -> Result<Value> {
+    match def {
+        TypeDef::Struct(fields) => Ok(Value::Struct(decode_fields(
+            fields, registry, data, depth, path, cursor,
+        )?)),
+        TypeDef::Enum(variants) => {
+            let tag = take(data, 1)?[0] as usize;
+            let variant = variants
+                .get(tag)
+                .ok_or_else(|| anyhow!("Enum tag {} out of range", tag))?;
+            Ok(Value::Enum(
+                variant.name.clone(),
+                decode_fields(&variant.fields, registry, data, depth, path, cursor)?,
+            ))
+        }
+    }
+}
+
+fn decode_fields(
+    fields: &[Field],
+    registry: &TypeRegistry,
+    data: &mut &[u8],
+    depth: usize,
+    path: &str,
+    cursor: &mut Cursor,
+) -> Result<Vec<(String, Value)>> {
+    fields
+        .iter()
+        .map(|field| {
+            let path = format!("{}.{}", path, field.name);
+            decode_type_at(&field.ty, registry, data, depth, &path, cursor)
+                .map(|value| (field.name.clone(), value))
+                .map_err(|e| anyhow!("{}: {}", field.name, e))
+        })
+        .collect()
+}
+
+fn decode_type_at(
+    ty: &FieldType,
+    registry: &TypeRegistry,
+    data: &mut &[u8],
+    depth: usize,
+    path: &str,
+    cursor: &mut Cursor,
+) -> Result<Value> {
+    let start = cursor.position(data);
+    let value = decode_value(ty, registry, data, depth, path, cursor)?;
+    cursor
+        .spans
+        .insert(path.to_string(), start..cursor.position(data));
+    Ok(value)
+}
+
+fn decode_value(
+    ty: &FieldType,
+    registry: &TypeRegistry,
+    data: &mut &[u8],
+    depth: usize,
+    path: &str,
+    cursor: &mut Cursor,
+) -> Result<Value> {
+    Ok(match ty {
+        FieldType::Bool => match take(data, 1)?[0] {
+            0 => Value::Bool(false),
+            1 => Value::Bool(true),
+            other => return Err(anyhow!("Invalid bool byte {}", other)),
+        },
+        FieldType::U8 => Value::Unsigned(take_array::<1>(data)?[0] as u128),
+        FieldType::U16 => Value::Unsigned(u16::from_le_bytes(take_array(data)?) as u128),
+        FieldType::U32 => Value::Unsigned(u32::from_le_bytes(take_array(data)?) as u128),
+        FieldType::U64 => Value::Unsigned(u64::from_le_bytes(take_array(data)?) as u128),
+        FieldType::U128 => Value::Unsigned(u128::from_le_bytes(take_array(data)?)),
+        FieldType::I8 => Value::Signed(i8::from_le_bytes(take_array(data)?) as i128),
+        FieldType::I16 => Value::Signed(i16::from_le_bytes(take_array(data)?) as i128),
+        FieldType::I32 => Value::Signed(i32::from_le_bytes(take_array(data)?) as i128),
+        FieldType::I64 => Value::Signed(i64::from_le_bytes(take_array(data)?) as i128),
+        FieldType::I128 => Value::Signed(i128::from_le_bytes(take_array(data)?)),
+        FieldType::F32 => Value::Float(f32::from_le_bytes(take_array(data)?) as f64),
+        FieldType::F64 => Value::Float(f64::from_le_bytes(take_array(data)?)),
+        FieldType::String => {
+            let len = take_len(data)?;
+            let bytes = take(data, len)?;
+            Value::String(String::from_utf8(bytes.to_vec()).map_err(|_| anyhow!("Invalid UTF-8"))?)
+        }
+        FieldType::Pubkey => Value::Pubkey(Pubkey::new_from_array(take_array(data)?)),
+        FieldType::Bytes => {
+            let len = take_len(data)?;
+            Value::Bytes(take(data, len)?.to_vec())
+        }
+        FieldType::Array(inner, len) if **inner == FieldType::U8 => {
+            Value::Bytes(take(data, *len)?.to_vec())
+        }
+        FieldType::Array(inner, len) => Value::Array(
+            (0..*len)
+                .map(|i| {
+                    let path = format!("{}[{}]", path, i);
+                    decode_type_at(inner, registry, data, depth, &path, cursor)
+                })
+                .collect::<Result<_>>()?,
+        ),
+        FieldType::Vec(inner) if **inner == FieldType::U8 => {
+            let len = take_len(data)?;
+            Value::Bytes(take(data, len)?.to_vec())
+        }
+        FieldType::Vec(inner) => {
+            let len = take_len(data)?;
+            Value::Array(
+                (0..len)
+                    .map(|i| {
+                        let path = format!("{}[{}]", path, i);
+                        decode_type_at(inner, registry, data, depth, &path, cursor)
+                    })
+                    .collect::<Result<_>>()?,
+            )
+        }
+        FieldType::Option(inner) => match take(data, 1)?[0] {
+            0 => Value::Option(None),
+            // The inner value shares the option's path, which spans the tag as well
+            1 => Value::Option(Some(Box::new(decode_value(
+                inner, registry, data, depth, path, cursor,
+            )?))),
+            other => return Err(anyhow!("Invalid option tag {}", other)),
+        },
+        FieldType::Defined(name) => {
+            if depth >= MAX_DEPTH {
+                return Err(anyhow!("Type nesting too deep at '{}'", name));
+            }
+            let def = registry
+                .get(name)
+                .ok_or_else(|| anyhow!("Unknown type '{}'", name))?;
+            decode_def_at(def, registry, data, depth + 1, path, cursor)?
+        }
+    })
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(anyhow!(
+            "Unexpected end of data: need {} bytes, have {}",
+            len,
+            data.len()
+        ));
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn take_array<const N: usize>(data: &mut &[u8]) -> Result<[u8; N]> {
+    let mut out = [0u8; N];
+    out.copy_from_slice(take(data, N)?);
+    Ok(out)
+}
+
+/// Borsh length prefix, rejected up front when it claims more elements than bytes remain.
+fn take_len(data: &mut &[u8]) -> Result<usize> {
+    let len = u32::from_le_bytes(take_array(data)?) as usize;
+    if len > data.len() {
+        return Err(anyhow!(
+            "Length prefix {} exceeds remaining {} bytes",
+            len,
+            data.len()
+        ));
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, ty: FieldType) -> Field {
+        Field {
+            name: name.into(),
+            ty,
+        }
+    }
+
+    #[test]
+    fn decodes_struct_and_leaves_trailing_bytes() {
+        let def = TypeDef::Struct(vec![
+            field("count", FieldType::U64),
+            field("bump", FieldType::U8),
+        ]);
+        let mut bytes = 5u64.to_le_bytes().to_vec();
+        bytes.extend([254, 0, 0]);
+        let mut data = bytes.as_slice();
+
+        let value = decode_def(&def, &TypeRegistry::default(), &mut data).unwrap();
+
+        assert_eq!(
+            value,
+            Value::Struct(vec![
+                ("count".into(), Value::Unsigned(5)),
+                ("bump".into(), Value::Unsigned(254)),
+            ])
+        );
+        assert_eq!(data.len(), 2);
+    }
+
+    #[test]
+    fn records_spans_from_account_offset() {
+        let def = TypeDef::Struct(vec![
+            field("maybe", FieldType::Option(Box::new(FieldType::U32))),
+            field("points", FieldType::Vec(Box::new(FieldType::I16))),
+        ]);
+        let mut bytes = vec![0xAA; 8];
+        bytes.extend([1, 7, 0, 0, 0]);
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend((-3i16).to_le_bytes());
+
+        let (_, spans) = decode_account(&def, &TypeRegistry::default(), &bytes, 8).unwrap();
+
+        assert_eq!(spans[""], 8..19);
+        assert_eq!(spans[".maybe"], 8..13);
+        assert_eq!(spans[".points"], 13..19);
+        assert_eq!(spans[".points[0]"], 17..19);
+    }
+
+    #[test]
+    fn decodes_option_vec_and_nested_struct() {
+        let mut registry = TypeRegistry::default();
+        registry.insert(
+            "Point",
+            TypeDef::Struct(vec![field("x", FieldType::I16), field("y", FieldType::I16)]),
+        );
+        let def = TypeDef::Struct(vec![
+            field("maybe", FieldType::Option(Box::new(FieldType::U32))),
+            field(
+                "points",
+                FieldType::Vec(Box::new(FieldType::Defined("Point".into()))),
+            ),
+        ]);
+
+        let mut bytes = vec![1];
+        bytes.extend(7u32.to_le_bytes());
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend((-3i16).to_le_bytes());
+        bytes.extend(4i16.to_le_bytes());
+        let mut data = bytes.as_slice();
+
+        let value = decode_def(&def, &registry, &mut data).unwrap();
+
+        assert_eq!(
+            value,
+            Value::Struct(vec![
+                (
+                    "maybe".into(),
+                    Value::Option(Some(Box::new(Value::Unsigned(7))))
+                ),
+                (
+                    "points".into(),
+                    Value::Array(vec![Value::Struct(vec![
+                        ("x".into(), Value::Signed(-3)),
+                        ("y".into(), Value::Signed(4)),
+                    ])])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let def = TypeDef::Struct(vec![field("count", FieldType::U64)]);
+        let mut data: &[u8] = &[1, 2, 3];
+        assert!(decode_def(&def, &TypeRegistry::default(), &mut data).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_length_prefix() {
+        let mut bytes = 1000u32.to_le_bytes().to_vec();
+        bytes.extend([0u8; 4]);
+        let mut data = bytes.as_slice();
+        assert!(decode_type(&FieldType::Bytes, &TypeRegistry::default(), &mut data).is_err());
+    }
+}
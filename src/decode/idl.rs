@@ -0,0 +1,253 @@
+use crate::decode::account::{AccountDecoder, DecodedAccount};
+use crate::decode::borsh::decode_account;
+use crate::decode::layout::{parse_type_def, TypeRegistry};
+use crate::models::types::AccountSnapshot;
+use crate::tx::error::{parse_error_codes, ErrorCodes};
+use anyhow::{anyhow, Result};
+use serde_json::Value as Json;
+use solana_sdk::hash::hashv;
+use solana_sdk::pubkey::Pubkey;
+
+/// Length of the Anchor account discriminator prefix.
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// An account type declared in an IDL's `accounts` section.
+#[derive(Debug, Clone)]
+pub struct IdlAccount {
+    pub name: String,
+    pub discriminator: [u8; DISCRIMINATOR_LEN],
+}
+
+/// The parts of an Anchor IDL needed to decode account data.
+/// Accepts both the 0.30+ format (explicit discriminators, `types` section)
+/// and the legacy format (inline account types, `publicKey`).
+#[derive(Debug, Clone)]
+pub struct Idl {
+    /// Program the IDL describes; when present, only accounts it owns are decoded.
+    pub address: Option<Pubkey>,
+    pub accounts: Vec<IdlAccount>,
+    /// Program error names from the `errors` section, keyed by code.
+    pub errors: ErrorCodes,
+    registry: TypeRegistry,
+}
+
+impl Idl {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read IDL {}: {}", path, e))?;
+        let json: Json = serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("Failed to parse IDL {}: {}", path, e))?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &Json) -> Result<Self> {
+        let address = match json
+            .get("address")
+            .or_else(|| json.pointer("/metadata/address"))
+            .and_then(|v| v.as_str())
+        {
+            Some(addr) => Some(addr.parse().map_err(|_| anyhow!("Invalid IDL address"))?),
+            None => None,
+        };
+
+        let mut registry = TypeRegistry::default();
+        if let Some(types) = json.get("types").and_then(|v| v.as_array()) {
+            registry.extend_from_json(types)?;
+        }
+
+        let mut accounts = Vec::new();
+        for entry in json
+            .get("accounts")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let name = entry
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("IDL account is missing 'name'"))?;
+
+            // Legacy IDLs declare the account layout inline
+            if let Some(def) = entry.get("type") {
+                registry.insert(name, parse_type_def(def)?);
+            }
+
+            let discriminator = match entry.get("discriminator").and_then(|v| v.as_array()) {
+                Some(bytes) => parse_discriminator(bytes)
+                    .ok_or_else(|| anyhow!("Invalid discriminator for account '{}'", name))?,
+                None => account_discriminator(name),
+            };
+
+            accounts.push(IdlAccount {
+                name: name.to_string(),
+                discriminator,
+            });
+        }
+
+        let errors = json
+            .get("errors")
+            .and_then(|v| v.as_array())
+            .map(|errors| parse_error_codes(errors))
+            .unwrap_or_default();
+
+        Ok(Self {
+            address,
+            accounts,
+            errors,
+            registry,
+        })
+    }
+}
+
+impl AccountDecoder for Idl {
+    fn decode(&self, snapshot: &AccountSnapshot) -> Option<DecodedAccount> {
+        if self
+            .address
+            .is_some_and(|program| program != snapshot.owner)
+        {
+            return None;
+        }
+
+        let discriminator = snapshot.data.get(..DISCRIMINATOR_LEN)?;
+        let account = self
+            .accounts
+            .iter()
+            .find(|a| a.discriminator == discriminator)?;
+        let def = self.registry.get(&account.name)?;
+
+        let (value, spans) =
+            decode_account(def, &self.registry, &snapshot.data, DISCRIMINATOR_LEN).ok()?;
+
+        Some(DecodedAccount {
+            type_name: account.name.clone(),
+            value,
+            spans,
+        })
+    }
+}
+
+/// Anchor's account discriminator: the first 8 bytes of `sha256("account:<Name>")`.
+pub fn account_discriminator(name: &str) -> [u8; DISCRIMINATOR_LEN] {
+    let hash = hashv(&[b"account:", name.as_bytes()]).to_bytes();
+    let mut out = [0u8; DISCRIMINATOR_LEN];
+    out.copy_from_slice(&hash[..DISCRIMINATOR_LEN]);
+    out
+}
+
+fn parse_discriminator(bytes: &[Json]) -> Option<[u8; DISCRIMINATOR_LEN]> {
+    if bytes.len() != DISCRIMINATOR_LEN {
+        return None;
+    }
+    let mut out = [0u8; DISCRIMINATOR_LEN];
+    for (slot, b) in out.iter_mut().zip(bytes) {
+        *slot = u8::try_from(b.as_u64()?).ok()?;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::borsh::Value;
+    use serde_json::json;
+
+    fn snapshot(owner: Pubkey, data: Vec<u8>) -> AccountSnapshot {
+        AccountSnapshot {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1,
+            owner,
+            executable: false,
+            data_len: data.len(),
+            data,
+            rent_epoch: 0,
+        }
+    }
+
+    fn counter_data(disc: [u8; 8], count: u64) -> Vec<u8> {
+        let mut data = disc.to_vec();
+        data.extend(count.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn decodes_modern_idl_account() {
+        let program = Pubkey::new_unique();
+        let idl = Idl::from_json(&json!({
+            "address": program.to_string(),
+            "accounts": [{"name": "Counter", "discriminator": [1, 2, 3, 4, 5, 6, 7, 8]}],
+            "types": [{
+                "name": "Counter",
+                "type": {"kind": "struct", "fields": [{"name": "count", "type": "u64"}]}
+            }],
+            "errors": [{"code": 6000, "name": "Overflow", "msg": "Counter overflowed"}]
+        }))
+        .unwrap();
+        assert_eq!(idl.errors[&6000], "Overflow");
+
+        let decoded = idl
+            .decode(&snapshot(
+                program,
+                counter_data([1, 2, 3, 4, 5, 6, 7, 8], 5),
+            ))
+            .unwrap();
+
+        assert_eq!(decoded.type_name, "Counter");
+        assert_eq!(
+            decoded.value,
+            Value::Struct(vec![("count".into(), Value::Unsigned(5))])
+        );
+    }
+
+    #[test]
+    fn decodes_legacy_idl_with_computed_discriminator() {
+        let idl = Idl::from_json(&json!({
+            "accounts": [{
+                "name": "Counter",
+                "type": {"kind": "struct", "fields": [{"name": "count", "type": "u64"}]}
+            }]
+        }))
+        .unwrap();
+
+        let data = counter_data(account_discriminator("Counter"), 9);
+        let decoded = idl.decode(&snapshot(Pubkey::new_unique(), data)).unwrap();
+
+        assert_eq!(
+            decoded.value,
+            Value::Struct(vec![("count".into(), Value::Unsigned(9))])
+        );
+    }
+
+    #[test]
+    fn ignores_accounts_owned_by_other_programs() {
+        let program = Pubkey::new_unique();
+        let idl = Idl::from_json(&json!({
+            "address": program.to_string(),
+            "accounts": [{"name": "Counter", "discriminator": [1, 2, 3, 4, 5, 6, 7, 8]}],
+            "types": [{
+                "name": "Counter",
+                "type": {"kind": "struct", "fields": [{"name": "count", "type": "u64"}]}
+            }]
+        }))
+        .unwrap();
+
+        let other = snapshot(
+            Pubkey::new_unique(),
+            counter_data([1, 2, 3, 4, 5, 6, 7, 8], 5),
+        );
+        assert!(idl.decode(&other).is_none());
+    }
+
+    #[test]
+    fn ignores_unknown_discriminator() {
+        let idl = Idl::from_json(&json!({
+            "accounts": [{
+                "name": "Counter",
+                "type": {"kind": "struct", "fields": [{"name": "count", "type": "u64"}]}
+            }]
+        }))
+        .unwrap();
+
+        let data = counter_data([0; 8], 1);
+        assert!(idl.decode(&snapshot(Pubkey::new_unique(), data)).is_none());
+    }
+}
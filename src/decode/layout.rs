@@ -0,0 +1,247 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value as Json;
+use std::collections::HashMap;
+
+/// The type of a single field in an account layout.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    String,
+    Pubkey,
+    Bytes,
+    Array(Box<FieldType>, usize),
+    Vec(Box<FieldType>),
+    Option(Box<FieldType>),
+    /// Reference to a named struct or enum in the same [`TypeRegistry`].
+    Defined(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub ty: FieldType,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeDef {
+    Struct(Vec<Field>),
+    Enum(Vec<Variant>),
+}
+
+/// Named type definitions that `FieldType::Defined` resolves against.
+#[derive(Debug, Clone, Default)]
+pub struct TypeRegistry {
+    types: HashMap<String, TypeDef>,
+}
+
+impl TypeRegistry {
+    pub fn insert(&mut self, name: impl Into<String>, def: TypeDef) {
+        self.types.insert(name.into(), def);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TypeDef> {
+        self.types.get(name)
+    }
+
+    /// Load every `{ "name": ..., "type": { "kind": ... } }` entry of a JSON array.
+    pub fn extend_from_json(&mut self, entries: &[Json]) -> Result<()> {
+        for entry in entries {
+            let name = entry
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("Type definition is missing 'name'"))?;
+            let def = entry
+                .get("type")
+                .ok_or_else(|| anyhow!("Type '{}' is missing 'type'", name))?;
+            self.insert(name, parse_type_def(def)?);
+        }
+        Ok(())
+    }
+}
+
+/// Parse a type in Anchor IDL syntax: `"u64"`, `{"vec": T}`, `{"option": T}`,
+/// `{"array": [T, N]}` or `{"defined": "Name"}` / `{"defined": {"name": "Name"}}`.
+pub fn parse_type(json: &Json) -> Result<FieldType> {
+    if let Some(name) = json.as_str() {
+        return Ok(match name {
+            "bool" => FieldType::Bool,
+            "u8" => FieldType::U8,
+            "u16" => FieldType::U16,
+            "u32" => FieldType::U32,
+            "u64" => FieldType::U64,
+            "u128" => FieldType::U128,
+            "i8" => FieldType::I8,
+            "i16" => FieldType::I16,
+            "i32" => FieldType::I32,
+            "i64" => FieldType::I64,
+            "i128" => FieldType::I128,
+            "f32" => FieldType::F32,
+            "f64" => FieldType::F64,
+            "string" => FieldType::String,
+            "pubkey" | "publicKey" => FieldType::Pubkey,
+            "bytes" => FieldType::Bytes,
+            other => return Err(anyhow!("Unsupported field type '{}'", other)),
+        });
+    }
+
+    if let Some(inner) = json.get("vec") {
+        return Ok(FieldType::Vec(Box::new(parse_type(inner)?)));
+    }
+
+    if let Some(inner) = json.get("option") {
+        return Ok(FieldType::Option(Box::new(parse_type(inner)?)));
+    }
+
+    if let Some(array) = json.get("array").and_then(|v| v.as_array()) {
+        let (inner, len) = match array.as_slice() {
+            [inner, len] => (inner, len),
+            _ => return Err(anyhow!("'array' must be [type, length]")),
+        };
+        let len = len
+            .as_u64()
+            .ok_or_else(|| anyhow!("Array length must be an integer"))?;
+        return Ok(FieldType::Array(Box::new(parse_type(inner)?), len as usize));
+    }
+
+    if let Some(defined) = json.get("defined") {
+        let name = defined
+            .as_str()
+            .or_else(|| defined.get("name").and_then(|v| v.as_str()))
+            .ok_or_else(|| anyhow!("'defined' must name a type"))?;
+        return Ok(FieldType::Defined(name.to_string()));
+    }
+
+    Err(anyhow!("Unsupported field type: {}", json))
+}
+
+/// Parse `{"kind": "struct", "fields": [...]}` or `{"kind": "enum", "variants": [...]}`.
+pub fn parse_type_def(json: &Json) -> Result<TypeDef> {
+    match json.get("kind").and_then(|v| v.as_str()) {
+        Some("struct") => Ok(TypeDef::Struct(parse_fields(json.get("fields"))?)),
+        Some("enum") => {
+            let variants = json
+                .get("variants")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| anyhow!("Enum is missing 'variants'"))?;
+            variants
+                .iter()
+                .map(|v| {
+                    let name = v
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .ok_or_else(|| anyhow!("Enum variant is missing 'name'"))?;
+                    Ok(Variant {
+                        name: name.to_string(),
+                        fields: parse_fields(v.get("fields"))?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+                .map(TypeDef::Enum)
+        }
+        other => Err(anyhow!("Unsupported type kind: {:?}", other)),
+    }
+}
+
+/// Parse named (`[{"name", "type"}]`) or tuple (`["u8", ...]`) fields.
+/// Tuple fields are named by position.
+pub fn parse_fields(json: Option<&Json>) -> Result<Vec<Field>> {
+    let fields = match json.and_then(|v| v.as_array()) {
+        Some(arr) => arr,
+        None => return Ok(Vec::new()),
+    };
+
+    fields
+        .iter()
+        .enumerate()
+        .map(
+            |(i, field)| match field.get("name").and_then(|v| v.as_str()) {
+                Some(name) => {
+                    let ty = field
+                        .get("type")
+                        .ok_or_else(|| anyhow!("Field '{}' is missing 'type'", name))?;
+                    Ok(Field {
+                        name: name.to_string(),
+                        ty: parse_type(ty)?,
+                    })
+                }
+                None => Ok(Field {
+                    name: i.to_string(),
+                    ty: parse_type(field)?,
+                }),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_primitive_and_composite_types() {
+        assert_eq!(parse_type(&json!("u64")).unwrap(), FieldType::U64);
+        assert_eq!(parse_type(&json!("publicKey")).unwrap(), FieldType::Pubkey);
+        assert_eq!(
+            parse_type(&json!({"array": ["u8", 32]})).unwrap(),
+            FieldType::Array(Box::new(FieldType::U8), 32)
+        );
+        assert_eq!(
+            parse_type(&json!({"option": {"vec": "u16"}})).unwrap(),
+            FieldType::Option(Box::new(FieldType::Vec(Box::new(FieldType::U16))))
+        );
+        assert_eq!(
+            parse_type(&json!({"defined": {"name": "Config"}})).unwrap(),
+            FieldType::Defined("Config".into())
+        );
+    }
+
+    #[test]
+    fn parses_enum_with_tuple_fields() {
+        let def = parse_type_def(&json!({
+            "kind": "enum",
+            "variants": [{"name": "Idle"}, {"name": "Active", "fields": ["u64"]}]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            def,
+            TypeDef::Enum(vec![
+                Variant {
+                    name: "Idle".into(),
+                    fields: vec![],
+                },
+                Variant {
+                    name: "Active".into(),
+                    fields: vec![Field {
+                        name: "0".into(),
+                        ty: FieldType::U64,
+                    }],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_type() {
+        assert!(parse_type(&json!("u256")).is_err());
+    }
+}
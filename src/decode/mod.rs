@@ -0,0 +1,6 @@
+pub mod account;
+pub mod borsh;
+pub mod idl;
+pub mod layout;
+pub mod schema;
+pub mod token;
@@ -0,0 +1,301 @@
+use crate::analysis::authority::BPF_LOADER_UPGRADEABLE_ID;
+use crate::decode::account::{AccountDecoder, DecodedAccount};
+use crate::decode::borsh::decode_account;
+use crate::decode::layout::{parse_fields, TypeDef, TypeRegistry};
+use crate::decode::token::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use crate::models::types::AccountSnapshot;
+use crate::tx::lookup_table::ADDRESS_LOOKUP_TABLE_PROGRAM_ID;
+use crate::tx::nonce::SYSTEM_PROGRAM_ID;
+use anyhow::{anyhow, Result};
+use serde_json::Value as Json;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+/// Owners whose accounts have a fixed meaning (system, sysvars, loaders,
+/// native programs, token). Layouts only apply to them when scoped.
+const BUILTIN_OWNERS: [Pubkey; 13] = [
+    SYSTEM_PROGRAM_ID,
+    pubkey!("Sysvar1111111111111111111111111111111111111"),
+    pubkey!("NativeLoader1111111111111111111111111111111"),
+    pubkey!("BPFLoader1111111111111111111111111111111111"),
+    pubkey!("BPFLoader2111111111111111111111111111111111"),
+    BPF_LOADER_UPGRADEABLE_ID,
+    pubkey!("LoaderV411111111111111111111111111111111111"),
+    pubkey!("Stake11111111111111111111111111111111111111"),
+    pubkey!("Vote111111111111111111111111111111111111111"),
+    pubkey!("Config1111111111111111111111111111111111111"),
+    ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+    TOKEN_PROGRAM_ID,
+    TOKEN_2022_PROGRAM_ID,
+];
+
+/// One account layout from a layout file, plus the selectors that decide
+/// which snapshots it applies to. Unset selectors match any account except
+/// those owned by a built-in program.
+#[derive(Debug, Clone)]
+pub struct LayoutAccount {
+    pub name: String,
+    pub owner: Option<Pubkey>,
+    pub address: Option<Pubkey>,
+    /// Prefix bytes that must match, and are skipped before decoding.
+    pub discriminator: Vec<u8>,
+    pub def: TypeDef,
+}
+
+impl LayoutAccount {
+    fn matches(&self, snapshot: &AccountSnapshot) -> bool {
+        // Without a selector naming them, built-in accounts keep their own meaning
+        if self.owner.is_none()
+            && self.address.is_none()
+            && BUILTIN_OWNERS.contains(&snapshot.owner)
+        {
+            return false;
+        }
+        self.owner.is_none_or(|owner| owner == snapshot.owner)
+            && self
+                .address
+                .is_none_or(|address| address == snapshot.pubkey)
+            && snapshot.data.starts_with(&self.discriminator)
+    }
+}
+
+/// User-supplied Borsh layouts for programs without an Anchor IDL.
+///
+/// ```json
+/// {
+///   "accounts": [
+///     { "name": "Counter", "owner": "<program id>",
+///       "fields": [{ "name": "count", "type": "u64" }] }
+///   ],
+///   "types": []
+/// }
+/// ```
+///
+/// Field types use Anchor IDL syntax (`u64`, `pubkey`, `{"array": ["u8", 32]}`,
+/// `{"option": T}`, `{"vec": T}`, `{"defined": "Name"}`); nested structs and
+/// enums go in `types`. The first account whose selectors match and whose
+/// data decodes is used.
+#[derive(Debug, Clone)]
+pub struct LayoutFile {
+    pub accounts: Vec<LayoutAccount>,
+    registry: TypeRegistry,
+}
+
+impl LayoutFile {
+    pub fn from_file(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read layout {}: {}", path, e))?;
+        let json: Json = serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("Failed to parse layout {}: {}", path, e))?;
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &Json) -> Result<Self> {
+        let mut registry = TypeRegistry::default();
+        if let Some(types) = json.get("types").and_then(|v| v.as_array()) {
+            registry.extend_from_json(types)?;
+        }
+
+        let entries = json
+            .get("accounts")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow!("Layout file must have an 'accounts' array"))?;
+
+        let accounts = entries
+            .iter()
+            .map(parse_account)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { accounts, registry })
+    }
+}
+
+impl AccountDecoder for LayoutFile {
+    fn decode(&self, snapshot: &AccountSnapshot) -> Option<DecodedAccount> {
+        self.accounts
+            .iter()
+            .filter(|account| account.matches(snapshot))
+            .find_map(|account| {
+                let (value, spans) = decode_account(
+                    &account.def,
+                    &self.registry,
+                    &snapshot.data,
+                    account.discriminator.len(),
+                )
+                .ok()?;
+                Some(DecodedAccount {
+                    type_name: account.name.clone(),
+                    value,
+                    spans,
+                })
+            })
+    }
+}
+
+fn parse_account(json: &Json) -> Result<LayoutAccount> {
+    let name = json
+        .get("name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("Layout account is missing 'name'"))?;
+
+    let pubkey_field = |key: &str| -> Result<Option<Pubkey>> {
+        match json.get(key).and_then(|v| v.as_str()) {
+            Some(s) => s
+                .parse()
+                .map(Some)
+                .map_err(|_| anyhow!("Invalid {} '{}' in layout '{}'", key, s, name)),
+            None => Ok(None),
+        }
+    };
+
+    let discriminator = match json.get("discriminator").and_then(|v| v.as_array()) {
+        Some(bytes) => bytes
+            .iter()
+            .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| anyhow!("Invalid discriminator in layout '{}'", name))?,
+        None => Vec::new(),
+    };
+
+    let fields = json
+        .get("fields")
+        .ok_or_else(|| anyhow!("Layout account '{}' is missing 'fields'", name))?;
+
+    Ok(LayoutAccount {
+        name: name.to_string(),
+        owner: pubkey_field("owner")?,
+        address: pubkey_field("address")?,
+        discriminator,
+        def: TypeDef::Struct(parse_fields(Some(fields))?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::borsh::Value;
+    use serde_json::json;
+
+    fn snapshot(owner: Pubkey, data: Vec<u8>) -> AccountSnapshot {
+        AccountSnapshot {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1,
+            owner,
+            executable: false,
+            data_len: data.len(),
+            data,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn decodes_native_counter() {
+        let program = Pubkey::new_unique();
+        let layout = LayoutFile::from_json(&json!({
+            "accounts": [{
+                "name": "Counter",
+                "owner": program.to_string(),
+                "fields": [{"name": "count", "type": "u64"}]
+            }]
+        }))
+        .unwrap();
+
+        let decoded = layout
+            .decode(&snapshot(program, 7u64.to_le_bytes().to_vec()))
+            .unwrap();
+
+        assert_eq!(
+            decoded.value,
+            Value::Struct(vec![("count".into(), Value::Unsigned(7))])
+        );
+        assert!(layout
+            .decode(&snapshot(Pubkey::new_unique(), 7u64.to_le_bytes().to_vec()))
+            .is_none());
+    }
+
+    #[test]
+    fn picks_layout_by_discriminator_with_nested_types() {
+        let layout = LayoutFile::from_json(&json!({
+            "accounts": [
+                {"name": "Config", "discriminator": [1], "fields": [{"name": "fee", "type": "u16"}]},
+                {"name": "Vault", "discriminator": [2], "fields": [
+                    {"name": "limits", "type": {"defined": "Limits"}},
+                    {"name": "tags", "type": {"array": ["u16", 2]}}
+                ]}
+            ],
+            "types": [{
+                "name": "Limits",
+                "type": {"kind": "struct", "fields": [{"name": "max", "type": {"option": "u32"}}]}
+            }]
+        }))
+        .unwrap();
+
+        let mut data = vec![2, 0];
+        data.extend(3u16.to_le_bytes());
+        data.extend(4u16.to_le_bytes());
+        let decoded = layout
+            .decode(&snapshot(Pubkey::new_unique(), data))
+            .unwrap();
+
+        assert_eq!(decoded.type_name, "Vault");
+        assert_eq!(
+            decoded.value,
+            Value::Struct(vec![
+                (
+                    "limits".into(),
+                    Value::Struct(vec![("max".into(), Value::Option(None))])
+                ),
+                (
+                    "tags".into(),
+                    Value::Array(vec![Value::Unsigned(3), Value::Unsigned(4)])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn unscoped_layout_leaves_builtin_accounts_alone() {
+        let layout = LayoutFile::from_json(&json!({
+            "accounts": [{"name": "Counter", "fields": [{"name": "count", "type": "u64"}]}]
+        }))
+        .unwrap();
+        let data = 7u64.to_le_bytes().to_vec();
+
+        assert!(layout
+            .decode(&snapshot(
+                crate::decode::token::TOKEN_PROGRAM_ID,
+                data.clone()
+            ))
+            .is_none());
+        assert!(layout
+            .decode(&snapshot(SYSTEM_PROGRAM_ID, data.clone()))
+            .is_none());
+        assert!(layout
+            .decode(&snapshot(BPF_LOADER_UPGRADEABLE_ID, data.clone()))
+            .is_none());
+        assert!(layout
+            .decode(&snapshot(Pubkey::new_unique(), data))
+            .is_some());
+    }
+
+    #[test]
+    fn owner_selector_reaches_builtin_accounts() {
+        let layout = LayoutFile::from_json(&json!({
+            "accounts": [{
+                "name": "Counter",
+                "owner": SYSTEM_PROGRAM_ID.to_string(),
+                "fields": [{"name": "count", "type": "u64"}]
+            }]
+        }))
+        .unwrap();
+
+        assert!(layout
+            .decode(&snapshot(SYSTEM_PROGRAM_ID, 7u64.to_le_bytes().to_vec()))
+            .is_some());
+    }
+
+    #[test]
+    fn rejects_layout_without_accounts() {
+        assert!(LayoutFile::from_json(&json!({"types": []})).is_err());
+    }
+}
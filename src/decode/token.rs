@@ -0,0 +1,392 @@
+use crate::decode::account::{AccountDecoder, DecodedAccount};
+use crate::decode::borsh::{Spans, Value};
+use crate::models::types::AccountSnapshot;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const MINT_LEN: usize = 82;
+const ACCOUNT_LEN: usize = 165;
+/// Token-2022 stores an account-type byte right after the base account layout.
+const ACCOUNT_TYPE_OFFSET: usize = ACCOUNT_LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+/// Freeze state of a token account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountState {
+    Uninitialized,
+    Initialized,
+    Frozen,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub delegate: Option<Pubkey>,
+    pub state: AccountState,
+    pub is_native: Option<u64>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mint {
+    pub mint_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<Pubkey>,
+}
+
+/// Base state of an SPL Token or Token-2022 account. Token-2022 extensions are not decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenState {
+    Account(TokenAccount),
+    Mint(Mint),
+}
+
+pub fn is_token_program(program: &Pubkey) -> bool {
+    *program == TOKEN_PROGRAM_ID || *program == TOKEN_2022_PROGRAM_ID
+}
+
+impl TokenAccount {
+    /// Encode in the base token-account layout, the inverse of `TokenState::unpack`.
+    pub fn pack(&self) -> Vec<u8> {
+        fn coption(present: bool, payload: &[u8], out: &mut Vec<u8>) {
+            out.extend((present as u32).to_le_bytes());
+            out.extend(payload);
+        }
+
+        let mut data = Vec::with_capacity(ACCOUNT_LEN);
+        data.extend(self.mint.to_bytes());
+        data.extend(self.owner.to_bytes());
+        data.extend(self.amount.to_le_bytes());
+        coption(
+            self.delegate.is_some(),
+            &self.delegate.unwrap_or_default().to_bytes(),
+            &mut data,
+        );
+        data.push(match self.state {
+            AccountState::Uninitialized => 0,
+            AccountState::Initialized => 1,
+            AccountState::Frozen => 2,
+        });
+        coption(
+            self.is_native.is_some(),
+            &self.is_native.unwrap_or_default().to_le_bytes(),
+            &mut data,
+        );
+        data.extend(self.delegated_amount.to_le_bytes());
+        coption(
+            self.close_authority.is_some(),
+            &self.close_authority.unwrap_or_default().to_bytes(),
+            &mut data,
+        );
+        data
+    }
+}
+
+impl TokenState {
+    /// Unpack the snapshot if it is owned by a token program and has a mint or
+    /// token-account layout. Multisig and uninitialized accounts yield `None`.
+    pub fn unpack(snapshot: &AccountSnapshot) -> Option<Self> {
+        if !is_token_program(&snapshot.owner) {
+            return None;
+        }
+
+        let data = &snapshot.data;
+        let state = match data.len() {
+            MINT_LEN => TokenState::Mint(unpack_mint(data)?),
+            ACCOUNT_LEN => TokenState::Account(unpack_account(data)?),
+            len if len > ACCOUNT_LEN && snapshot.owner == TOKEN_2022_PROGRAM_ID => {
+                match data[ACCOUNT_TYPE_OFFSET] {
+                    ACCOUNT_TYPE_MINT => TokenState::Mint(unpack_mint(data)?),
+                    ACCOUNT_TYPE_ACCOUNT => TokenState::Account(unpack_account(data)?),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        match &state {
+            TokenState::Account(a) if a.state == AccountState::Uninitialized => None,
+            TokenState::Mint(m) if !m.is_initialized => None,
+            _ => Some(state),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            TokenState::Account(_) => "TokenAccount",
+            TokenState::Mint(_) => "Mint",
+        }
+    }
+
+    /// The decoded fields and their spans in the fixed base layout.
+    fn to_value(&self) -> (Value, Spans) {
+        // (name, encoded length, value); a `COption<T>` is a 4-byte tag followed by `T`
+        let fields = match self {
+            TokenState::Account(a) => vec![
+                ("mint", 32, Value::Pubkey(a.mint)),
+                ("owner", 32, Value::Pubkey(a.owner)),
+                ("amount", 8, Value::Unsigned(a.amount as u128)),
+                ("delegate", 36, pubkey_option(a.delegate)),
+                (
+                    "state",
+                    1,
+                    Value::Enum(format!("{:?}", a.state), Vec::new()),
+                ),
+                (
+                    "is_native",
+                    12,
+                    Value::Option(a.is_native.map(|v| Box::new(Value::Unsigned(v as u128)))),
+                ),
+                (
+                    "delegated_amount",
+                    8,
+                    Value::Unsigned(a.delegated_amount as u128),
+                ),
+                ("close_authority", 36, pubkey_option(a.close_authority)),
+            ],
+            TokenState::Mint(m) => vec![
+                ("mint_authority", 36, pubkey_option(m.mint_authority)),
+                ("supply", 8, Value::Unsigned(m.supply as u128)),
+                ("decimals", 1, Value::Unsigned(m.decimals as u128)),
+                ("is_initialized", 1, Value::Bool(m.is_initialized)),
+                ("freeze_authority", 36, pubkey_option(m.freeze_authority)),
+            ],
+        };
+
+        let mut spans = Spans::new();
+        let mut offset = 0;
+        let mut values = Vec::with_capacity(fields.len());
+        for (name, len, value) in fields {
+            spans.insert(format!(".{}", name), offset..offset + len);
+            offset += len;
+            values.push((name.to_string(), value));
+        }
+        spans.insert(String::new(), 0..offset);
+
+        (Value::Struct(values), spans)
+    }
+}
+
+/// Built-in decoder for SPL Token and Token-2022 mints and token accounts.
+pub struct TokenDecoder;
+
+impl AccountDecoder for TokenDecoder {
+    fn decode(&self, snapshot: &AccountSnapshot) -> Option<DecodedAccount> {
+        let state = TokenState::unpack(snapshot)?;
+        let (value, spans) = state.to_value();
+        Some(DecodedAccount {
+            type_name: state.type_name().to_string(),
+            value,
+            spans,
+        })
+    }
+}
+
+fn pubkey_option(key: Option<Pubkey>) -> Value {
+    Value::Option(key.map(|k| Box::new(Value::Pubkey(k))))
+}
+
+fn unpack_mint(data: &[u8]) -> Option<Mint> {
+    let mut r = Reader(data);
+    Some(Mint {
+        mint_authority: r.coption_pubkey()?,
+        supply: r.u64()?,
+        decimals: r.u8()?,
+        is_initialized: match r.u8()? {
+            0 => false,
+            1 => true,
+            _ => return None,
+        },
+        freeze_authority: r.coption_pubkey()?,
+    })
+}
+
+fn unpack_account(data: &[u8]) -> Option<TokenAccount> {
+    let mut r = Reader(data);
+    Some(TokenAccount {
+        mint: r.pubkey()?,
+        owner: r.pubkey()?,
+        amount: r.u64()?,
+        delegate: r.coption_pubkey()?,
+        state: match r.u8()? {
+            0 => AccountState::Uninitialized,
+            1 => AccountState::Initialized,
+            2 => AccountState::Frozen,
+            _ => return None,
+        },
+        is_native: r.coption_u64()?,
+        delegated_amount: r.u64()?,
+        close_authority: r.coption_pubkey()?,
+    })
+}
+
+/// Little-endian reader for the fixed-size `Pack` layouts used by the token programs.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, tail) = self.0.split_first_chunk::<N>()?;
+        self.0 = tail;
+        Some(*head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|b| b[0])
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn pubkey(&mut self) -> Option<Pubkey> {
+        self.take().map(Pubkey::new_from_array)
+    }
+
+    /// `COption` uses a 4-byte tag, and the payload is present even when the tag is 0.
+    fn coption_tag(&mut self) -> Option<bool> {
+        match u32::from_le_bytes(self.take()?) {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn coption_pubkey(&mut self) -> Option<Option<Pubkey>> {
+        let present = self.coption_tag()?;
+        let key = self.pubkey()?;
+        Some(present.then_some(key))
+    }
+
+    fn coption_u64(&mut self) -> Option<Option<u64>> {
+        let present = self.coption_tag()?;
+        let value = self.u64()?;
+        Some(present.then_some(value))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn coption_pubkey(key: Option<Pubkey>) -> Vec<u8> {
+        let mut out = (key.is_some() as u32).to_le_bytes().to_vec();
+        out.extend(key.unwrap_or_default().to_bytes());
+        out
+    }
+
+    pub(crate) fn token_account_data(
+        amount: u64,
+        delegate: Option<Pubkey>,
+        frozen: bool,
+    ) -> Vec<u8> {
+        let mut data = Vec::with_capacity(ACCOUNT_LEN);
+        data.extend(Pubkey::new_from_array([1; 32]).to_bytes());
+        data.extend(Pubkey::new_from_array([2; 32]).to_bytes());
+        data.extend(amount.to_le_bytes());
+        data.extend(coption_pubkey(delegate));
+        data.push(if frozen { 2 } else { 1 });
+        data.extend([0u8; 12]);
+        data.extend(0u64.to_le_bytes());
+        data.extend(coption_pubkey(None));
+        data
+    }
+
+    pub(crate) fn mint_data(supply: u64, authority: Option<Pubkey>) -> Vec<u8> {
+        let mut data = coption_pubkey(authority);
+        data.extend(supply.to_le_bytes());
+        data.push(6);
+        data.push(1);
+        data.extend(coption_pubkey(None));
+        data
+    }
+
+    fn snapshot(owner: Pubkey, data: Vec<u8>) -> AccountSnapshot {
+        AccountSnapshot {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1,
+            owner,
+            executable: false,
+            data_len: data.len(),
+            data,
+            rent_epoch: 0,
+        }
+    }
+
+    #[test]
+    fn unpacks_token_account() {
+        let delegate = Pubkey::new_unique();
+        let data = token_account_data(500, Some(delegate), true);
+        let state = TokenState::unpack(&snapshot(TOKEN_PROGRAM_ID, data)).unwrap();
+
+        let TokenState::Account(account) = state else {
+            panic!("expected token account");
+        };
+        assert_eq!(account.amount, 500);
+        assert_eq!(account.delegate, Some(delegate));
+        assert_eq!(account.state, AccountState::Frozen);
+        assert_eq!(account.close_authority, None);
+    }
+
+    #[test]
+    fn packs_token_account() {
+        let account = TokenAccount {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount: 42,
+            delegate: Some(Pubkey::new_unique()),
+            state: AccountState::Frozen,
+            is_native: None,
+            delegated_amount: 7,
+            close_authority: None,
+        };
+        let data = account.pack();
+
+        assert_eq!(data.len(), ACCOUNT_LEN);
+        assert_eq!(
+            TokenState::unpack(&snapshot(TOKEN_PROGRAM_ID, data)),
+            Some(TokenState::Account(account))
+        );
+    }
+
+    #[test]
+    fn unpacks_mint() {
+        let authority = Pubkey::new_unique();
+        let data = mint_data(1_000, Some(authority));
+        let state = TokenState::unpack(&snapshot(TOKEN_PROGRAM_ID, data)).unwrap();
+
+        assert_eq!(
+            state,
+            TokenState::Mint(Mint {
+                mint_authority: Some(authority),
+                supply: 1_000,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: None,
+            })
+        );
+    }
+
+    #[test]
+    fn unpacks_token_2022_account_with_extensions() {
+        let mut data = token_account_data(42, None, false);
+        data.push(ACCOUNT_TYPE_ACCOUNT);
+        data.extend([0u8; 16]);
+        let state = TokenState::unpack(&snapshot(TOKEN_2022_PROGRAM_ID, data)).unwrap();
+        assert!(matches!(state, TokenState::Account(a) if a.amount == 42));
+    }
+
+    #[test]
+    fn ignores_other_owners() {
+        let data = token_account_data(1, None, false);
+        assert!(TokenState::unpack(&snapshot(Pubkey::new_unique(), data)).is_none());
+    }
+}
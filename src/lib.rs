@@ -1,5 +1,7 @@
 pub mod analysis;
 pub mod cli;
+pub mod decode;
 pub mod models;
 pub mod report;
 pub mod rpc;
+pub mod tx;
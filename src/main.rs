@@ -1,45 +1,403 @@
 use anyhow::{anyhow, Result};
 use clap::Parser;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::collections::HashMap;
 
-use solaudit::analysis::engine::analyse;
-use solaudit::cli::args::Cli;
+use solaudit::analysis::blockhash::{apply_blockhash_status, BlockhashStatus};
+use solaudit::analysis::compute::{apply_compute_report, ComputeReport};
+use solaudit::analysis::engine::{
+    analyse, AnalysisContext, AnalysisResult, RetrySafety, SimulationOutcome,
+};
+use solaudit::analysis::expect::Expectations;
+use solaudit::analysis::idempotency::{apply_double_execution, compare_runs, SecondRun};
+use solaudit::analysis::ignore::{IgnoreRule, IgnoreRules};
+use solaudit::analysis::landed::{apply_landed_status, LandedStatus};
+use solaudit::analysis::nonce::{analyse_nonce, apply_durable_nonce};
+use solaudit::analysis::policy::Policy;
+use solaudit::analysis::postmortem::{analyse_recorded, recorded_fee};
+use solaudit::cli::args::{Cli, Command};
+use solaudit::cli::config::Config;
+use solaudit::decode::idl::Idl;
+use solaudit::decode::schema::LayoutFile;
 use solaudit::report::writer::{print_json, print_text};
-use solaudit::rpc::client::SolanaRpc;
+use solaudit::rpc::client::{SignatureStatus, SolanaRpc};
+use solaudit::tx::decode::{decode_transaction, DecodedTransaction};
+use solaudit::tx::error::ErrorNames;
+use solaudit::tx::fee::FeeEstimate;
+use solaudit::tx::nonce::durable_nonce;
+use solaudit::tx::replay::repeat_instructions;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    let fail_on = cli
+        .fail_on
+        .as_deref()
+        .map(str::parse::<RetrySafety>)
+        .transpose()
+        .map_err(|e| anyhow!(e))?;
+
+    let ignore = ignore_rules(&cli)?;
+    let expectations = cli
+        .expect
+        .as_deref()
+        .map(Expectations::from_file)
+        .transpose()?;
+
     let rpc = SolanaRpc::new(&cli.cluster)?;
 
-    let before = rpc
-        .fetch_snapshot(&cli.program)
-        .await
-        .map_err(|e| anyhow!("Failed to fetch pre-state: {}", e))?;
+    let mut result = match &cli.command {
+        Some(Command::Tx { signature }) => analyse_landed(&cli, &rpc, ignore, signature).await?,
+        None => analyse_simulated(&cli, &rpc, ignore).await?,
+    };
+
+    if let Some(expectations) = &expectations {
+        result.assertions = expectations.check(&result);
+    }
+
+    match cli.output.as_str() {
+        "json" => print_json(&result),
+        _ => print_text(&result),
+    }
+
+    if let Some(threshold) = fail_on {
+        if result.classification.safety >= threshold {
+            std::process::exit(2);
+        }
+    }
+
+    if result.assertions.iter().any(|a| !a.passed) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Simulate `--tx` (or only snapshot `--account`s) and analyse the diff.
+async fn analyse_simulated(
+    cli: &Cli,
+    rpc: &SolanaRpc,
+    ignore: IgnoreRules,
+) -> Result<AnalysisResult> {
+    let mut decoded = decoded_transaction(cli)?;
+    if let Some(tx) = decoded.as_mut().filter(|tx| tx.has_unresolved_lookups()) {
+        if let Err(e) = resolve_lookup_tables(rpc, tx).await {
+            if cli.accounts.is_empty() {
+                return Err(e);
+            }
+            eprintln!("Could not resolve address lookup tables: {}", e);
+        }
+    }
+    let nonce = decoded.as_ref().and_then(durable_nonce);
+    let mut accounts = watched_accounts(cli, decoded.as_ref())?;
+    if let Some(nonce) = &nonce {
+        let address = nonce.nonce_account.to_string();
+        if !accounts.contains(&address) {
+            eprintln!("Watching durable nonce account {}", address);
+            accounts.push(address);
+        }
+    }
 
-    let (after, simulation_logs) = if let Some(tx_base64) = &cli.tx {
-        let sim = rpc.simulate_transaction(tx_base64, &cli.program).await?;
+    let mut befores = Vec::with_capacity(accounts.len());
+    for address in &accounts {
+        let before = rpc
+            .fetch_snapshot_or_default(address)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch pre-state for {}: {}", address, e))?;
+        befores.push(before);
+    }
+
+    let (afters, simulation, units_consumed) = if let Some(tx_base64) = &cli.tx {
+        let sim = rpc.simulate_transaction(tx_base64, &accounts).await?;
 
         if let Some(err) = &sim.error {
             eprintln!("Simulation error: {}", err);
         }
 
-        if let Some(units) = sim.units_consumed {
-            eprintln!("Compute units consumed: {}", units);
+        let simulation = SimulationOutcome {
+            error: sim.error,
+            logs: sim.logs,
+        };
+        (sim.post_snapshots, simulation, sim.units_consumed)
+    } else {
+        (
+            befores.iter().cloned().map(Some).collect(),
+            SimulationOutcome::default(),
+            None,
+        )
+    };
+
+    let rent = match rpc.rent().await {
+        Ok(rent) => Some(rent),
+        Err(e) => {
+            eprintln!("Skipping rent-exemption check: {}", e);
+            None
+        }
+    };
+
+    let mut ctx = analysis_context(cli, ignore, decoded.as_ref())?;
+    ctx.rent = rent;
+
+    let first_run_ok = simulation.error.is_none();
+    let mut result = analyse(befores.into_iter().zip(afters).collect(), simulation, &ctx);
+
+    if let Some(decoded) = &decoded {
+        let report = ComputeReport::new(
+            decoded,
+            units_consumed,
+            &result.invocations,
+            cli.compute_margin,
+        );
+        apply_compute_report(&mut result, report);
+    }
+
+    if let (true, Some(tx_base64)) = (cli.double_run, &cli.tx) {
+        if first_run_ok {
+            let second = match repeat_instructions(tx_base64) {
+                Ok(doubled) => rpc
+                    .simulate_transaction(&doubled.tx_base64, &accounts)
+                    .await
+                    .map(|sim| SecondRun {
+                        post_snapshots: sim.post_snapshots,
+                        error: sim.error,
+                        repeated_from: doubled.repeated_from,
+                    })
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            let double = compare_runs(&result, second, &ctx);
+            apply_double_execution(&mut result, double);
+        } else {
+            eprintln!("Skipping double execution: the first simulation failed");
         }
+    }
+
+    if let Some(nonce) = &nonce {
+        let analysis = analyse_nonce(&result, nonce);
+        apply_durable_nonce(&mut result, analysis);
+    } else if let Some(decoded) = &decoded {
+        // A durable nonce never expires, so only a real recent blockhash is checked
+        match blockhash_status(rpc, decoded, cli.last_valid_block_height).await {
+            Ok(status) => apply_blockhash_status(&mut result, status),
+            Err(e) => eprintln!("Skipping blockhash check: {}", e),
+        }
+    }
+
+    if let Some(signature) = decoded.as_ref().and_then(|tx| tx.signature()) {
+        match rpc.signature_status(&signature).await {
+            Ok(status) => apply_signature_status(&mut result, &signature, status, &ctx),
+            Err(e) => eprintln!("Skipping signature status check: {}", e),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Rebuild what a landed transaction did from its `getTransaction` metadata.
+async fn analyse_landed(
+    cli: &Cli,
+    rpc: &SolanaRpc,
+    ignore: IgnoreRules,
+    signature: &str,
+) -> Result<AnalysisResult> {
+    if cli.tx.is_some() {
+        return Err(anyhow!("--tx cannot be combined with the tx subcommand"));
+    }
+
+    let signature: Signature = signature
+        .parse()
+        .map_err(|_| anyhow!("Invalid signature: {}", signature))?;
+    let record = rpc
+        .transaction(&signature)
+        .await?
+        .ok_or_else(|| anyhow!("Transaction {} not found on the cluster", signature))?;
 
-        let after = sim.post_snapshot.unwrap_or_else(|| before.clone());
-        (after, sim.logs)
+    let watched = if cli.accounts.is_empty() {
+        record.transaction.writable_accounts()
     } else {
-        (before.clone(), Vec::new())
+        cli.accounts
+            .iter()
+            .map(|a| a.parse().map_err(|_| anyhow!("Invalid account: {}", a)))
+            .collect::<Result<Vec<Pubkey>>>()?
     };
 
-    let result = analyse(before, after, simulation_logs);
+    let mut ctx = analysis_context(cli, ignore, Some(&record.transaction))?;
+    ctx.fee = recorded_fee(&record);
+    ctx.recorded_state = true;
 
-    match cli.output.as_str() {
-        "json" => print_json(&result),
-        _ => print_text(&result),
+    // The metadata has no owners; the current ones stand in for owner policy rules
+    let mut owners = HashMap::new();
+    for pubkey in &watched {
+        let account = rpc
+            .fetch_snapshot_or_default(&pubkey.to_string())
+            .await
+            .map_err(|e| anyhow!("Failed to fetch account {}: {}", pubkey, e))?;
+        owners.insert(*pubkey, account.owner);
     }
 
-    Ok(())
+    let mut result = analyse_recorded(&record, &watched, &owners, &ctx)?;
+
+    let report = ComputeReport::new(
+        &record.transaction,
+        record.compute_units_consumed,
+        &result.invocations,
+        cli.compute_margin,
+    );
+    apply_compute_report(&mut result, report);
+
+    // Prefer the live status for its confirmation level; the record proves it landed
+    let status = match rpc.signature_status(&signature).await {
+        Ok(Some(status)) => status,
+        _ => SignatureStatus {
+            slot: record.slot,
+            confirmation_status: None,
+            error: record.error.clone(),
+        },
+    };
+    apply_signature_status(&mut result, &signature, Some(status), &ctx);
+
+    Ok(result)
+}
+
+/// Decoders, rules and transaction-derived settings shared by both analyses.
+fn analysis_context(
+    cli: &Cli,
+    ignore: IgnoreRules,
+    decoded: Option<&DecodedTransaction>,
+) -> Result<AnalysisContext> {
+    let mut error_names = ErrorNames::default();
+    if let Some(decoded) = decoded {
+        error_names.instruction_programs = decoded
+            .instructions
+            .iter()
+            .map(|ix| decoded.program_id(ix).unwrap_or_default())
+            .collect();
+    }
+
+    let mut ctx = AnalysisContext {
+        ignore,
+        fee: decoded.and_then(FeeEstimate::from_transaction),
+        writable_accounts: decoded.map(|tx| tx.writable_accounts()),
+        policy: match &cli.policy {
+            Some(path) => Policy::from_file(path)?,
+            None => Policy::default(),
+        },
+        error_names,
+        ..Default::default()
+    };
+    if let Some(path) = &cli.idl {
+        let idl = Idl::from_file(path)?;
+        ctx.error_names.idls.push((idl.address, idl.errors.clone()));
+        ctx.decoders.push(Box::new(idl));
+    }
+    if let Some(path) = &cli.layout {
+        ctx.decoders.push(Box::new(LayoutFile::from_file(path)?));
+    }
+
+    Ok(ctx)
+}
+
+fn apply_signature_status(
+    result: &mut AnalysisResult,
+    signature: &Signature,
+    mut status: Option<SignatureStatus>,
+    ctx: &AnalysisContext,
+) {
+    if let Some(err) = status.as_mut().and_then(|s| s.error.as_mut()) {
+        err.resolve_name(&ctx.error_names);
+    }
+    apply_landed_status(result, LandedStatus::new(signature.to_string(), status));
+}
+
+async fn blockhash_status(
+    rpc: &SolanaRpc,
+    tx: &DecodedTransaction,
+    last_valid_block_height: Option<u64>,
+) -> Result<BlockhashStatus> {
+    let valid = rpc.is_blockhash_valid(&tx.recent_blockhash).await?;
+    let current = rpc.block_height().await?;
+    Ok(BlockhashStatus::new(
+        tx.recent_blockhash.to_string(),
+        valid,
+        current,
+        last_valid_block_height,
+    ))
+}
+
+/// Load the address lookup tables a v0 message refers to and resolve its loaded accounts.
+async fn resolve_lookup_tables(rpc: &SolanaRpc, tx: &mut DecodedTransaction) -> Result<()> {
+    let mut tables = HashMap::new();
+    for lookup in &tx.address_table_lookups {
+        let addresses = rpc.lookup_table(&lookup.account_key).await.map_err(|e| {
+            anyhow!(
+                "Failed to fetch address lookup table {}: {}",
+                lookup.account_key,
+                e
+            )
+        })?;
+        tables.insert(lookup.account_key, addresses);
+    }
+    tx.resolve_lookups(&tables)
+}
+
+/// Default rules, then rules from `--config`, then `--ignore` flags.
+fn ignore_rules(cli: &Cli) -> Result<IgnoreRules> {
+    let mut rules = if cli.no_default_ignores {
+        IgnoreRules::empty()
+    } else {
+        IgnoreRules::default()
+    };
+
+    let config = match &cli.config {
+        Some(path) => Config::from_file(path)?,
+        None => Config::default(),
+    };
+
+    for rule in config.ignore.iter().chain(&cli.ignores) {
+        rules.rules.push(IgnoreRule::parse(rule)?);
+    }
+
+    Ok(rules)
+}
+
+/// Decode `--tx`. With explicit `--account`s the transaction is only forwarded to the
+/// simulator, so a message we cannot decode is a warning rather than an error.
+fn decoded_transaction(cli: &Cli) -> Result<Option<DecodedTransaction>> {
+    let Some(tx_base64) = &cli.tx else {
+        return Ok(None);
+    };
+
+    match decode_transaction(tx_base64) {
+        Ok(decoded) => Ok(Some(decoded)),
+        Err(e) if !cli.accounts.is_empty() => {
+            eprintln!("Could not decode transaction: {}", e);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Accounts named with `--account`, or every writable account in `--tx` when none are given.
+fn watched_accounts(cli: &Cli, decoded: Option<&DecodedTransaction>) -> Result<Vec<String>> {
+    if !cli.accounts.is_empty() {
+        return Ok(cli.accounts.clone());
+    }
+
+    let decoded = decoded
+        .ok_or_else(|| anyhow!("Pass at least one --account, or --tx to discover accounts"))?;
+
+    let writable: Vec<String> = decoded
+        .writable_accounts()
+        .iter()
+        .map(|pubkey| pubkey.to_string())
+        .collect();
+
+    if writable.is_empty() {
+        return Err(anyhow!("Transaction has no writable accounts to diff"));
+    }
+
+    eprintln!("Discovered {} writable account(s)", writable.len());
+    Ok(writable)
 }
@@ -1 +1,2 @@
+pub mod rent;
 pub mod types;
@@ -0,0 +1,55 @@
+use serde::Serialize;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+pub const RENT_SYSVAR_ID: Pubkey = pubkey!("SysvarRent111111111111111111111111111111111");
+
+/// Bytes of account metadata charged on top of the data length.
+pub const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+
+/// Rent parameters of a cluster, as stored in the Rent sysvar.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Rent {
+    pub lamports_per_byte_year: u64,
+    /// Years of rent an account must hold to be exempt.
+    pub exemption_threshold: f64,
+    pub burn_percent: u8,
+}
+
+impl Rent {
+    /// Decode the sysvar's bincode layout: u64, f64, u8.
+    pub fn from_sysvar_data(data: &[u8]) -> Option<Self> {
+        Some(Self {
+            lamports_per_byte_year: u64::from_le_bytes(data.get(..8)?.try_into().ok()?),
+            exemption_threshold: f64::from_le_bytes(data.get(8..16)?.try_into().ok()?),
+            burn_percent: *data.get(16)?,
+        })
+    }
+
+    /// Lamports an account with `data_len` bytes needs to be rent exempt.
+    pub fn minimum_balance(&self, data_len: usize) -> u64 {
+        let bytes = ACCOUNT_STORAGE_OVERHEAD + data_len as u64;
+        ((bytes * self.lamports_per_byte_year) as f64 * self.exemption_threshold) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_sysvar_and_computes_minimum_balance() {
+        let mut data = 3480u64.to_le_bytes().to_vec();
+        data.extend(2.0f64.to_le_bytes());
+        data.push(50);
+
+        let rent = Rent::from_sysvar_data(&data).unwrap();
+        assert_eq!(rent.lamports_per_byte_year, 3480);
+        assert_eq!(rent.burn_percent, 50);
+        // Well-known mainnet values: an empty account needs 890_880 lamports
+        assert_eq!(rent.minimum_balance(0), 890_880);
+        assert_eq!(rent.minimum_balance(165), 2_039_280);
+
+        assert!(Rent::from_sysvar_data(&data[..16]).is_none());
+    }
+}
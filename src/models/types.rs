@@ -11,3 +11,23 @@ pub struct AccountSnapshot {
     pub data: Vec<u8>,
     pub rent_epoch: u64,
 }
+
+impl AccountSnapshot {
+    /// The state of an account that does not exist on-chain.
+    pub fn empty(pubkey: Pubkey) -> Self {
+        Self {
+            pubkey,
+            lamports: 0,
+            owner: Pubkey::default(),
+            executable: false,
+            data_len: 0,
+            data: vec![],
+            rent_epoch: 0,
+        }
+    }
+
+    /// An account with no lamports is removed by the runtime, so it does not exist.
+    pub fn exists(&self) -> bool {
+        self.lamports > 0
+    }
+}
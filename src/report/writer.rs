@@ -1,58 +1,440 @@
-use crate::analysis::engine::AnalysisResult;
+use crate::analysis::blockhash::{BlockhashStatus, REPLACED_BLOCKHASH_NOTE};
+use crate::analysis::compute::ComputeReport;
+use crate::analysis::conservation::LamportConservation;
+use crate::analysis::cpi::{InvocationNode, InvocationOutcome};
+use crate::analysis::engine::{to_hex, AccountAnalysis, AnalysisResult, ByteRange, ChangeKind};
+use crate::analysis::expect::AssertionResult;
+use crate::analysis::fields::delta_suffix;
+use crate::analysis::idempotency::DoubleExecution;
+use crate::analysis::landed::LandedStatus;
+use crate::analysis::nonce::NonceAnalysis;
+use crate::analysis::token::TokenDiff;
+use solana_sdk::pubkey::Pubkey;
+
+/// Bytes shown per side of a changed range before the text output truncates.
+const MAX_TEXT_BYTES: usize = 32;
 
 pub fn print_text(result: &AnalysisResult) {
     println!("——— Retry Safety Report ———");
 
-    println!("Account: {}", result.before.pubkey);
+    println!("Accounts: {}", result.accounts.len());
+
+    println!(
+        "Safety: {} — {}",
+        result.classification.safety, result.classification.description
+    );
+
+    print_authority_findings(&result.accounts);
+
+    for account in &result.accounts {
+        print_account(account);
+    }
+
+    if let Some(err) = &result.simulation_error {
+        println!("\nSimulation Error: {}", err);
+    }
+
+    if let Some(conservation) = &result.lamport_conservation {
+        print_conservation(conservation);
+    }
+
+    if !result.classification.reasons.is_empty() {
+        println!("\nReasons: ");
+        for r in &result.classification.reasons {
+            println!("- {}", r);
+        }
+    }
+
+    if let Some(double) = &result.double_execution {
+        print_double_execution(double);
+    }
+
+    if let Some(nonce) = &result.durable_nonce {
+        print_nonce(nonce);
+    }
+
+    if let Some(blockhash) = &result.blockhash {
+        print_blockhash(blockhash);
+    }
+
+    if let Some(landed) = &result.landed {
+        print_landed(landed);
+    }
+
+    if let Some(compute) = &result.compute {
+        print_compute(compute);
+    }
+
+    if !result.assertions.is_empty() {
+        print_assertions(&result.assertions);
+    }
+
+    if !result.invocations.is_empty() {
+        println!("\nCall Tree:");
+        for node in &result.invocations {
+            print_invocation(node);
+        }
+    } else if !result.simulation_logs.is_empty() {
+        println!("\nSimulation Logs:");
+        for log in &result.simulation_logs {
+            println!("  {}", log);
+        }
+    }
+}
+
+fn print_invocation(node: &InvocationNode) {
+    let indent = "  ".repeat(node.depth.saturating_sub(1));
+    let outcome = match &node.outcome {
+        InvocationOutcome::Success => "success".to_string(),
+        InvocationOutcome::Failed(reason) => format!("failed: {}", reason),
+        InvocationOutcome::Incomplete => "incomplete".to_string(),
+    };
+    let units = match (node.compute_units_consumed, node.compute_units_available) {
+        (Some(consumed), Some(available)) => format!(", {} of {} CU", consumed, available),
+        (Some(consumed), None) => format!(", {} CU", consumed),
+        _ => String::new(),
+    };
+    println!(
+        "{}- {} [{}] {}{}",
+        indent, node.program_id, node.depth, outcome, units
+    );
+    for log in &node.logs {
+        println!("{}    {}", indent, log);
+    }
+    for child in &node.children {
+        print_invocation(child);
+    }
+}
+
+/// Authority changes across all accounts, ahead of the per-account diffs.
+fn print_authority_findings(accounts: &[AccountAnalysis]) {
+    let findings: Vec<_> = accounts
+        .iter()
+        .flat_map(|a| {
+            a.authority_changes
+                .iter()
+                .map(move |c| (a.before.pubkey, c))
+        })
+        .collect();
+    if findings.is_empty() {
+        return;
+    }
+
+    println!("\nAuthority Findings:");
+    for (pubkey, change) in findings {
+        println!("- [{}] {}: {}", change.severity, pubkey, change);
+    }
+}
+
+fn print_conservation(conservation: &LamportConservation) {
+    let status = if conservation.balanced {
+        "balanced".to_string()
+    } else {
+        format!("imbalance of {:+} lamports", conservation.imbalance)
+    };
+    println!("\nLamport Conservation: {}", status);
+    println!(
+        "- {} before, {} after, {} fee",
+        conservation.before_total, conservation.after_total, conservation.fee
+    );
+    for pubkey in &conservation.unwatched {
+        println!("- Not snapshotted: {}", pubkey);
+    }
+}
+
+fn print_landed(status: &LandedStatus) {
+    println!("\nSignature: {}", status.signature);
+    match (status.landed, &status.error) {
+        (false, _) => println!("- Not landed"),
+        (true, None) => println!(
+            "- Landed ({}) in slot {}: DO NOT RETRY",
+            status.confirmation_status.as_deref().unwrap_or("processed"),
+            status.slot.unwrap_or_default()
+        ),
+        (true, Some(err)) => println!(
+            "- Landed and failed in slot {}: {}",
+            status.slot.unwrap_or_default(),
+            err
+        ),
+    }
+}
+
+fn print_compute(compute: &ComputeReport) {
+    let source = if compute.requested_limit.is_some() {
+        "requested"
+    } else {
+        "default"
+    };
+    println!(
+        "\nCompute Budget: {} unit limit ({})",
+        compute.effective_limit, source
+    );
+    if let Some(price) = compute.unit_price {
+        println!("- Unit price: {} micro-lamports", price);
+    }
+    if let (Some(consumed), Some(headroom)) = (compute.units_consumed, compute.headroom) {
+        println!("- Consumed: {} ({} headroom)", consumed, headroom);
+    }
+    for ix in &compute.instructions {
+        match ix.units_consumed {
+            Some(units) => println!(
+                "- Instruction {} ({}): {} units",
+                ix.index, ix.program_id, units
+            ),
+            None => println!(
+                "- Instruction {} ({}): not reported",
+                ix.index, ix.program_id
+            ),
+        }
+    }
+    if compute.below_margin {
+        println!(
+            "- WARNING: headroom below the {}% margin",
+            compute.margin_percent
+        );
+    }
+}
+
+fn print_blockhash(status: &BlockhashStatus) {
+    let validity = if status.valid { "valid" } else { "expired" };
+    println!("\nRecent Blockhash: {} ({})", status.blockhash, validity);
+    println!("- Current block height: {}", status.current_block_height);
+    if let Some(last) = status.last_valid_block_height {
+        println!("- Last valid block height: {}", last);
+    }
+    if let Some(blocks) = status.blocks_remaining {
+        println!("- Blocks remaining: {}", blocks);
+    }
+    println!("- Note: {}", REPLACED_BLOCKHASH_NOTE);
+}
+
+fn print_nonce(nonce: &NonceAnalysis) {
+    println!("\nDurable Nonce: {}", nonce.nonce_account);
+    println!("- Signed with: {}", nonce.transaction_nonce);
+    if let Some(stored) = &nonce.stored_nonce {
+        println!("- Stored: {}", stored);
+    }
+    if nonce.advanced {
+        println!("- Advanced by the simulation");
+    }
+    println!("- {}", nonce.explanation);
+}
+
+fn print_assertions(assertions: &[AssertionResult]) {
+    let failed = assertions.iter().filter(|a| !a.passed).count();
+    println!(
+        "\nAssertions: {} passed, {} failed",
+        assertions.len() - failed,
+        failed
+    );
+    for a in assertions {
+        let mark = if a.passed { "ok" } else { "FAILED" };
+        let subject = a
+            .pubkey
+            .map_or_else(|| "transaction".to_string(), |p| p.to_string());
+        println!(
+            "- [{}] {} {}: expected {}, got {}",
+            mark, subject, a.target, a.expected, a.actual
+        );
+    }
+}
+
+fn print_double_execution(double: &DoubleExecution) {
+    println!("\nDouble Execution: {}", double.verdict);
+    println!("- {}", double.summary);
+
+    if let Some(err) = &double.second_run_error {
+        println!("- Second run error: {}", err);
+    }
+
+    for repeated in &double.repeated_changes {
+        println!("- {} changed again:", repeated.pubkey);
+        for change in &repeated.changes {
+            println!("  {}", change);
+        }
+    }
+}
+
+fn print_account(account: &AccountAnalysis) {
+    println!("\nAccount: {}", account.before.pubkey);
+
+    println!("Safety: {}", account.classification.safety);
+
+    if let Some(policy) = &account.policy {
+        match &policy.message {
+            Some(message) => println!("Policy Rule {}: {}", policy.rule, message),
+            None => println!("Policy Rule {}", policy.rule),
+        }
+    }
+
+    if !account.diff.ignored.is_empty() {
+        println!("Ignored: {}", account.diff.ignored.join(", "));
+    }
+
+    match account.diff.change_kind {
+        ChangeKind::Created => println!("Account Created"),
+        ChangeKind::Closed => println!("Account Closed"),
+        ChangeKind::Unchanged | ChangeKind::Modified => {}
+    }
 
-    println!("Safety: {:?}", result.classification.safety);
+    if let Some(fee) = account.diff.fee_deducted {
+        println!("Fee Paid: {} lamports (fee payer)", fee);
+    }
 
-    if result.classification.reasons.is_empty() {
+    if account.classification.reasons.is_empty() && account.authority_changes.is_empty() {
         println!("No state changes detected");
         return;
     }
 
-    println!("\nState Changes:");
+    println!("State Changes:");
 
-    if result.before.lamports != result.after.lamports {
+    if account.diff.lamports_changed {
         println!(
-            "- Lamports: {} -> {}",
-            result.before.lamports, result.after.lamports
+            "- Lamports: {} -> {}{}",
+            account.before.lamports,
+            account.after.lamports,
+            delta_suffix(account.diff.lamports_delta, account.diff.lamports_percent)
         );
     }
 
-    if result.before.owner != result.after.owner {
-        println!("- Owner: {} -> {}", result.before.owner, result.after.owner);
+    if account.diff.owner_changed {
+        println!(
+            "- Owner: {} -> {}",
+            account.before.owner, account.after.owner
+        );
     }
 
-    if result.before.executable != result.after.executable {
+    if account.diff.executable_changed {
         println!(
             "- Executable: {} -> {}",
-            result.before.executable, result.after.executable
+            account.before.executable, account.after.executable
         );
     }
 
-    if result.before.data_len != result.after.data_len {
+    if account.diff.data_len_changed {
         println!(
             "- Data Size: {} -> {}",
-            result.before.data_len, result.after.data_len
+            account.before.data_len, account.after.data_len
         );
     }
 
-    if result.diff.data_changed {
-        println!("- Data Content: changed");
+    if account.diff.rent_epoch_changed {
+        println!(
+            "- Rent Epoch: {} -> {}",
+            account.before.rent_epoch, account.after.rent_epoch
+        );
     }
 
-    println!("\nReasons: ");
-    for r in &result.classification.reasons {
-        println!("- {}", r);
+    if let Some(decoded) = account
+        .decoded_after
+        .as_ref()
+        .or(account.decoded_before.as_ref())
+    {
+        println!("- Decoded As: {}", decoded.type_name);
     }
 
-    if !result.simulation_logs.is_empty() {
-        println!("\nSimulation Logs:");
-        for log in &result.simulation_logs {
-            println!("  {}", log);
+    if let Some(rent) = account.rent.as_ref().filter(|r| !r.rent_exempt) {
+        println!(
+            "- Rent: {} lamports, {} required for {} bytes (below exemption)",
+            rent.lamports, rent.minimum_balance, rent.data_len
+        );
+    }
+
+    if let Some(token) = account.token.as_ref().filter(|t| t.has_changes()) {
+        print_token(token);
+    }
+
+    for change in &account.diff.field_changes {
+        println!("- {}", change);
+    }
+
+    if account.diff.data_changed {
+        println!(
+            "- Data Content: {} range(s) changed",
+            account.diff.data_ranges.len()
+        );
+        for range in &account.diff.data_ranges {
+            print_range(range);
+        }
+    }
+}
+
+fn print_token(token: &TokenDiff) {
+    match token {
+        TokenDiff::Account {
+            mint,
+            amount_before,
+            amount_after,
+            amount_delta,
+            delegate,
+            delegated_amount,
+            close_authority,
+            frozen,
+        } => {
+            println!("- Token Account (mint {}):", mint);
+            if *amount_delta != 0 {
+                println!(
+                    "  Amount: {} -> {} ({:+})",
+                    amount_before, amount_after, amount_delta
+                );
+            }
+            if let Some(c) = delegate {
+                println!(
+                    "  Delegate: {} -> {}",
+                    display_key(&c.before),
+                    display_key(&c.after)
+                );
+            }
+            if let Some(c) = delegated_amount {
+                println!("  Delegated Amount: {} -> {}", c.before, c.after);
+            }
+            if let Some(c) = close_authority {
+                println!(
+                    "  Close Authority: {} -> {}",
+                    display_key(&c.before),
+                    display_key(&c.after)
+                );
+            }
+            if let Some(c) = frozen {
+                println!("  Frozen: {} -> {}", c.before, c.after);
+            }
         }
+        TokenDiff::Mint {
+            supply_before,
+            supply_after,
+            supply_delta,
+        } => {
+            println!(
+                "- Mint Supply: {} -> {} ({:+})",
+                supply_before, supply_after, supply_delta
+            );
+        }
+    }
+}
+
+fn display_key(key: &Option<Pubkey>) -> String {
+    key.map_or_else(|| "none".to_string(), |k| k.to_string())
+}
+
+fn print_range(range: &ByteRange) {
+    println!(
+        "  [{}..{}] ({} byte{}): {} -> {}",
+        range.offset,
+        range.offset + range.length,
+        range.length,
+        if range.length == 1 { "" } else { "s" },
+        truncated_hex(&range.old),
+        truncated_hex(&range.new)
+    );
+}
+
+fn truncated_hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        "(none)".to_string()
+    } else if bytes.len() > MAX_TEXT_BYTES {
+        format!("{}…", to_hex(&bytes[..MAX_TEXT_BYTES]))
+    } else {
+        to_hex(bytes)
     }
 }
 
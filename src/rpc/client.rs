@@ -1,21 +1,41 @@
+use crate::models::rent::{Rent, RENT_SYSVAR_ID};
 use crate::models::types::AccountSnapshot;
+use crate::tx::error::TransactionError;
+use crate::tx::lookup_table::{lookup_table_addresses, ADDRESS_LOOKUP_TABLE_PROGRAM_ID};
+use crate::tx::recorded::RecordedTransaction;
 use anyhow::{anyhow, Result};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_request::RpcRequest;
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::sync::OnceCell;
 
 /// Result of a transaction simulation, including simulated account states.
 pub struct SimulationResult {
-    pub error: Option<String>,
+    pub error: Option<TransactionError>,
     pub logs: Vec<String>,
-    pub post_snapshot: Option<AccountSnapshot>,
+    /// Post-state for each watched address, in the order they were requested.
+    pub post_snapshots: Vec<Option<AccountSnapshot>>,
     pub units_consumed: Option<u64>,
 }
 
+/// Where a transaction signature stands on the cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureStatus {
+    pub slot: u64,
+    /// `processed`, `confirmed` or `finalized`.
+    pub confirmation_status: Option<String>,
+    /// The transaction error, when it landed but failed.
+    pub error: Option<TransactionError>,
+}
+
 pub struct SolanaRpc {
     client: RpcClient,
+    rent: OnceCell<Rent>,
 }
 
 impl SolanaRpc {
@@ -40,7 +60,108 @@ impl SolanaRpc {
 
         let client = RpcClient::new(url);
 
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            rent: OnceCell::new(),
+        })
+    }
+
+    /// Checked at `confirmed`: a blockhash from a block that is not yet finalized
+    /// is still one a transaction can land with.
+    pub async fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool> {
+        Ok(self
+            .client
+            .is_blockhash_valid(blockhash, CommitmentConfig::confirmed())
+            .await?)
+    }
+
+    /// The `confirmed` block height, to match [`Self::is_blockhash_valid`].
+    pub async fn block_height(&self) -> Result<u64> {
+        Ok(self
+            .client
+            .get_block_height_with_commitment(CommitmentConfig::confirmed())
+            .await?)
+    }
+
+    /// Look up a signature, searching the full transaction history.
+    /// Returns `None` when the cluster has not seen it.
+    pub async fn signature_status(&self, signature: &Signature) -> Result<Option<SignatureStatus>> {
+        let params = serde_json::json!([
+            [signature.to_string()],
+            { "searchTransactionHistory": true }
+        ]);
+
+        let response: serde_json::Value = self
+            .client
+            .send(RpcRequest::GetSignatureStatuses, params)
+            .await?;
+
+        let status = match response
+            .get("value")
+            .and_then(|v| v.as_array())
+            .and_then(|arr| arr.first())
+        {
+            Some(status) if !status.is_null() => status,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(SignatureStatus {
+            slot: status
+                .get("slot")
+                .and_then(|v| v.as_u64())
+                .ok_or_else(|| anyhow!("Missing slot in signature status"))?,
+            confirmation_status: status
+                .get("confirmationStatus")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            error: status
+                .get("err")
+                .filter(|v| !v.is_null())
+                .map(TransactionError::from_json),
+        }))
+    }
+
+    /// Fetch a landed transaction with its metadata.
+    /// Returns `None` when the cluster has no record of it.
+    pub async fn transaction(&self, signature: &Signature) -> Result<Option<RecordedTransaction>> {
+        let params = serde_json::json!([
+            signature.to_string(),
+            {
+                "encoding": "base64",
+                "commitment": "confirmed",
+                "maxSupportedTransactionVersion": 0
+            }
+        ]);
+
+        let response: serde_json::Value =
+            self.client.send(RpcRequest::GetTransaction, params).await?;
+
+        if response.is_null() {
+            return Ok(None);
+        }
+        RecordedTransaction::from_json(&response).map(Some)
+    }
+
+    /// Addresses stored in an address lookup table account.
+    pub async fn lookup_table(&self, table: &Pubkey) -> Result<Vec<Pubkey>> {
+        let account = self.client.get_account(table).await?;
+        if account.owner != ADDRESS_LOOKUP_TABLE_PROGRAM_ID {
+            return Err(anyhow!("{} is not an address lookup table", table));
+        }
+        lookup_table_addresses(&account.data)
+    }
+
+    /// The cluster's rent parameters, fetched from the Rent sysvar on first use.
+    pub async fn rent(&self) -> Result<Rent> {
+        let rent = self
+            .rent
+            .get_or_try_init(|| async {
+                let account = self.client.get_account(&RENT_SYSVAR_ID).await?;
+                Rent::from_sysvar_data(&account.data)
+                    .ok_or_else(|| anyhow!("Rent sysvar data is too short"))
+            })
+            .await?;
+        Ok(*rent)
     }
 
     /// Like `fetch_snapshot`, but returns a zeroed-out snapshot when the account
@@ -50,16 +171,7 @@ impl SolanaRpc {
         match self.fetch_snapshot(address).await {
             Ok(snapshot) => Ok(snapshot),
             Err(e) if e.to_string().contains("AccountNotFound") => {
-                let pubkey: Pubkey = address.parse()?;
-                Ok(AccountSnapshot {
-                    pubkey,
-                    lamports: 0,
-                    owner: Pubkey::default(),
-                    executable: false,
-                    data_len: 0,
-                    data: vec![],
-                    rent_epoch: 0,
-                })
+                Ok(AccountSnapshot::empty(address.parse()?))
             }
             Err(e) => Err(e),
         }
@@ -82,12 +194,14 @@ impl SolanaRpc {
         })
     }
 
-    /// Simulate a base64-encoded transaction and return the post-state for the watched account.
-    /// Uses `simulateTransaction` with accounts config. No on-chain state is mutated.
+    /// Simulate a base64-encoded transaction and return the post-state for every watched account.
+    /// Uses `simulateTransaction` with accounts config, requesting all addresses in one call.
+    /// The recent blockhash is replaced, so an expired transaction still simulates.
+    /// No on-chain state is mutated.
     pub async fn simulate_transaction(
         &self,
         tx_base64: &str,
-        watch_address: &str,
+        watch_addresses: &[String],
     ) -> Result<SimulationResult> {
         STANDARD
             .decode(tx_base64)
@@ -102,7 +216,7 @@ impl SolanaRpc {
                 "replaceRecentBlockhash": true,
                 "accounts": {
                     "encoding": "base64",
-                    "addresses": [watch_address]
+                    "addresses": watch_addresses
                 }
             }
         ]);
@@ -112,106 +226,173 @@ impl SolanaRpc {
             .send(RpcRequest::SimulateTransaction, params)
             .await?;
 
-        // simulateTransaction returns { context: {...}, value: { err, logs, accounts, ... } }
-        let sim = response
-            .get("value")
-            .ok_or_else(|| anyhow!("simulateTransaction response missing 'value' field"))?;
+        parse_simulation(&response, watch_addresses)
+    }
+}
 
-        let error = sim.get("err").and_then(|v| {
-            if v.is_null() {
-                None
-            } else {
-                Some(format!("{}", v))
-            }
-        });
+/// Parse a `simulateTransaction` response for the accounts it was asked to watch.
+fn parse_simulation(
+    response: &serde_json::Value,
+    watch_addresses: &[String],
+) -> Result<SimulationResult> {
+    // simulateTransaction returns { context: {...}, value: { err, logs, accounts, ... } }
+    let sim = response
+        .get("value")
+        .ok_or_else(|| anyhow!("simulateTransaction response missing 'value' field"))?;
 
-        let logs = sim
-            .get("logs")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(String::from))
-                    .collect()
-            })
-            .unwrap_or_default();
+    let error = sim
+        .get("err")
+        .filter(|v| !v.is_null())
+        .map(TransactionError::from_json);
+
+    let logs = sim
+        .get("logs")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let units_consumed = sim.get("unitsConsumed").and_then(|v| v.as_u64());
 
-        let units_consumed = sim.get("unitsConsumed").and_then(|v| v.as_u64());
+    let post_snapshots = if error.is_some() {
+        // A failed simulation reports every account as null: there is no post-state
+        vec![None; watch_addresses.len()]
+    } else {
+        parse_simulated_accounts(sim, watch_addresses)?
+    };
 
-        let post_snapshot = self.parse_simulated_account(sim, watch_address)?;
+    Ok(SimulationResult {
+        error,
+        logs,
+        post_snapshots,
+        units_consumed,
+    })
+}
 
-        Ok(SimulationResult {
-            error,
-            logs,
-            post_snapshot,
-            units_consumed,
+fn parse_simulated_accounts(
+    response: &serde_json::Value,
+    addresses: &[String],
+) -> Result<Vec<Option<AccountSnapshot>>> {
+    let accounts = match response.get("accounts").and_then(|v| v.as_array()) {
+        Some(arr) => arr,
+        None => return Ok(vec![None; addresses.len()]),
+    };
+
+    // Entries come back in the same order as the requested addresses.
+    // After a successful simulation, a null entry is an account that no longer exists.
+    addresses
+        .iter()
+        .enumerate()
+        .map(|(i, address)| match accounts.get(i) {
+            Some(v) if v.is_null() => Ok(Some(AccountSnapshot::empty(address.parse()?))),
+            Some(v) => parse_simulated_account(v, address).map(Some),
+            None => Ok(None),
         })
-    }
+        .collect()
+}
 
-    fn parse_simulated_account(
-        &self,
-        response: &serde_json::Value,
-        address: &str,
-    ) -> Result<Option<AccountSnapshot>> {
-        let accounts = match response.get("accounts").and_then(|v| v.as_array()) {
-            Some(arr) => arr,
-            None => return Ok(None),
-        };
+fn parse_simulated_account(
+    account_value: &serde_json::Value,
+    address: &str,
+) -> Result<AccountSnapshot> {
+    let pubkey: Pubkey = address.parse()?;
 
-        // We requested one address, so we expect one entry
-        let account_value =
-            match accounts
-                .first()
-                .and_then(|v| if v.is_null() { None } else { Some(v) })
-            {
-                Some(v) => v,
-                None => return Ok(None),
-            };
+    let lamports = account_value
+        .get("lamports")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow!("Missing lamports in simulated account"))?;
 
-        let pubkey: Pubkey = address.parse()?;
+    let owner: Pubkey = account_value
+        .get("owner")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("Missing owner in simulated account"))?
+        .parse()?;
 
-        let lamports = account_value
-            .get("lamports")
-            .and_then(|v| v.as_u64())
-            .ok_or_else(|| anyhow!("Missing lamports in simulated account"))?;
-
-        let owner: Pubkey = account_value
-            .get("owner")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing owner in simulated account"))?
-            .parse()?;
-
-        let executable = account_value
-            .get("executable")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-
-        let rent_epoch = account_value
-            .get("rentEpoch")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0);
-
-        let data = match account_value.get("data").and_then(|v| v.as_array()) {
-            Some(arr) => {
-                // Format: ["<base64 data>", "base64"]
-                let encoded = arr.first().and_then(|v| v.as_str()).unwrap_or("");
-                if encoded.is_empty() {
-                    vec![]
-                } else {
-                    STANDARD.decode(encoded).unwrap_or_default()
-                }
+    let executable = account_value
+        .get("executable")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let rent_epoch = account_value
+        .get("rentEpoch")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+
+    let data = match account_value.get("data").and_then(|v| v.as_array()) {
+        Some(arr) => {
+            // Format: ["<base64 data>", "base64"]
+            let encoded = arr.first().and_then(|v| v.as_str()).unwrap_or("");
+            if encoded.is_empty() {
+                vec![]
+            } else {
+                STANDARD.decode(encoded).unwrap_or_default()
             }
-            None => vec![],
-        };
-        let data_len = data.len();
+        }
+        None => vec![],
+    };
+    let data_len = data.len();
 
-        Ok(Some(AccountSnapshot {
-            pubkey,
-            lamports,
-            owner,
-            executable,
-            data_len,
-            data,
-            rent_epoch,
-        }))
+    Ok(AccountSnapshot {
+        pubkey,
+        lamports,
+        owner,
+        executable,
+        data_len,
+        data,
+        rent_epoch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::engine::{
+        analyse, AnalysisContext, ChangeKind, RetrySafety, SimulationOutcome,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn failed_simulation_has_no_post_state() {
+        let pubkey = Pubkey::new_unique();
+        let response = json!({
+            "context": {"slot": 1},
+            "value": {
+                "err": {"InstructionError": [0, {"Custom": 1}]},
+                "logs": [],
+                "accounts": [null],
+                "unitsConsumed": 0
+            }
+        });
+
+        let sim = parse_simulation(&response, &[pubkey.to_string()]).unwrap();
+        assert!(sim.post_snapshots[0].is_none());
+
+        let mut before = AccountSnapshot::empty(pubkey);
+        before.lamports = 1_000_000;
+        let result = analyse(
+            vec![(before, sim.post_snapshots[0].clone())],
+            SimulationOutcome {
+                error: sim.error,
+                logs: sim.logs,
+            },
+            &AnalysisContext::default(),
+        );
+
+        assert_ne!(result.accounts[0].diff.change_kind, ChangeKind::Closed);
+        assert_eq!(result.classification.safety, RetrySafety::Unknown);
+    }
+
+    #[test]
+    fn null_account_after_success_is_closed() {
+        let pubkey = Pubkey::new_unique();
+        let response = json!({"value": {"err": null, "accounts": [null]}});
+
+        let sim = parse_simulation(&response, &[pubkey.to_string()]).unwrap();
+        let after = sim.post_snapshots[0].as_ref().unwrap();
+        assert_eq!(after.pubkey, pubkey);
+        assert!(!after.exists());
     }
 }
@@ -0,0 +1,122 @@
+use crate::analysis::authority::BPF_LOADER_UPGRADEABLE_ID;
+use crate::tx::nonce::SYSTEM_PROGRAM_ID;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+    pubkey!("ComputeBudget111111111111111111111111111111");
+
+/// Upper bound on compute units a transaction may request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Units granted per instruction when no limit is requested.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Units granted per builtin-program instruction when no limit is requested.
+pub const DEFAULT_BUILTIN_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 3_000;
+
+/// Programs the runtime still executes natively. Builtins migrated to BPF
+/// (Stake, Config, Address Lookup Table) get the regular allowance.
+const BUILTIN_PROGRAM_IDS: [Pubkey; 8] = [
+    SYSTEM_PROGRAM_ID,
+    COMPUTE_BUDGET_PROGRAM_ID,
+    pubkey!("Vote111111111111111111111111111111111111111"),
+    pubkey!("BPFLoader1111111111111111111111111111111111"),
+    pubkey!("BPFLoader2111111111111111111111111111111111"),
+    BPF_LOADER_UPGRADEABLE_ID,
+    pubkey!("LoaderV411111111111111111111111111111111111"),
+    pubkey!("ZkE1Gama1Proof11111111111111111111111111111"),
+];
+
+/// The limit a transaction runs under when it requests none: the default
+/// allowance of each instruction's program, capped at `MAX_COMPUTE_UNIT_LIMIT`.
+pub fn default_compute_unit_limit(program_ids: impl IntoIterator<Item = Pubkey>) -> u32 {
+    program_ids
+        .into_iter()
+        .map(|program_id| {
+            if BUILTIN_PROGRAM_IDS.contains(&program_id) {
+                DEFAULT_BUILTIN_INSTRUCTION_COMPUTE_UNIT_LIMIT
+            } else {
+                DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+            }
+        })
+        .fold(0u32, u32::saturating_add)
+        .min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// A decoded Compute Budget program instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeBudgetInstruction {
+    RequestHeapFrame(u32),
+    SetComputeUnitLimit(u32),
+    /// Price in micro-lamports per compute unit.
+    SetComputeUnitPrice(u64),
+    SetLoadedAccountsDataSizeLimit(u32),
+}
+
+impl ComputeBudgetInstruction {
+    /// Decode instruction data (a one-byte Borsh enum tag followed by the value).
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let (tag, rest) = data.split_first()?;
+        let u32_arg = || {
+            rest.get(..4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        };
+        match tag {
+            1 => u32_arg().map(Self::RequestHeapFrame),
+            2 => u32_arg().map(Self::SetComputeUnitLimit),
+            3 => rest
+                .get(..8)
+                .map(|b| Self::SetComputeUnitPrice(u64::from_le_bytes(b.try_into().unwrap()))),
+            4 => u32_arg().map(Self::SetLoadedAccountsDataSizeLimit),
+            _ => None,
+        }
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        match self {
+            Self::RequestHeapFrame(v) => [&[1u8][..], &v.to_le_bytes()].concat(),
+            Self::SetComputeUnitLimit(v) => [&[2u8][..], &v.to_le_bytes()].concat(),
+            Self::SetComputeUnitPrice(v) => [&[3u8][..], &v.to_le_bytes()].concat(),
+            Self::SetLoadedAccountsDataSizeLimit(v) => [&[4u8][..], &v.to_le_bytes()].concat(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_instructions() {
+        for ix in [
+            ComputeBudgetInstruction::RequestHeapFrame(64 * 1024),
+            ComputeBudgetInstruction::SetComputeUnitLimit(300_000),
+            ComputeBudgetInstruction::SetComputeUnitPrice(5_000),
+            ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(1024),
+        ] {
+            assert_eq!(ComputeBudgetInstruction::unpack(&ix.pack()), Some(ix));
+        }
+    }
+
+    #[test]
+    fn default_limit_gives_builtins_a_smaller_allowance() {
+        let program = Pubkey::new_unique();
+        assert_eq!(
+            default_compute_unit_limit([COMPUTE_BUDGET_PROGRAM_ID, SYSTEM_PROGRAM_ID, program]),
+            206_000
+        );
+        assert_eq!(
+            default_compute_unit_limit([program; 8]),
+            MAX_COMPUTE_UNIT_LIMIT
+        );
+        assert_eq!(default_compute_unit_limit([]), 0);
+    }
+
+    #[test]
+    fn rejects_unknown_or_short_data() {
+        assert_eq!(ComputeBudgetInstruction::unpack(&[0]), None);
+        assert_eq!(ComputeBudgetInstruction::unpack(&[2, 1, 2]), None);
+        assert_eq!(ComputeBudgetInstruction::unpack(&[]), None);
+    }
+}
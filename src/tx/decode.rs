@@ -0,0 +1,327 @@
+use crate::tx::compute_budget::{ComputeBudgetInstruction, COMPUTE_BUDGET_PROGRAM_ID};
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::compiled_instruction::CompiledInstruction;
+use solana_sdk::message::v0::{LoadedAddresses, MessageAddressTableLookup};
+use solana_sdk::message::{MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+
+/// A wire transaction decoded from the base64 blob passed via `--tx`.
+/// Legacy and v0 messages are both accepted.
+#[derive(Debug, Clone)]
+pub struct DecodedTransaction {
+    pub signatures: Vec<Signature>,
+    pub header: MessageHeader,
+    /// Account keys stored in the message itself.
+    pub account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Hash,
+    pub instructions: Vec<CompiledInstruction>,
+    /// Address lookup tables a v0 message loads further accounts from.
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+    /// Accounts loaded through `address_table_lookups`, once resolved.
+    pub loaded_addresses: LoadedAddresses,
+}
+
+impl From<VersionedTransaction> for DecodedTransaction {
+    fn from(tx: VersionedTransaction) -> Self {
+        let (header, account_keys, recent_blockhash, instructions, address_table_lookups) =
+            match tx.message {
+                VersionedMessage::Legacy(m) => (
+                    m.header,
+                    m.account_keys,
+                    m.recent_blockhash,
+                    m.instructions,
+                    Vec::new(),
+                ),
+                VersionedMessage::V0(m) => (
+                    m.header,
+                    m.account_keys,
+                    m.recent_blockhash,
+                    m.instructions,
+                    m.address_table_lookups,
+                ),
+            };
+
+        Self {
+            signatures: tx.signatures,
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups,
+            loaded_addresses: LoadedAddresses::default(),
+        }
+    }
+}
+
+impl DecodedTransaction {
+    /// Whether the message loads accounts from lookup tables that are not resolved yet.
+    pub fn has_unresolved_lookups(&self) -> bool {
+        !self.address_table_lookups.is_empty() && self.loaded_addresses.is_empty()
+    }
+
+    /// Resolve `address_table_lookups` against the addresses stored in each table.
+    /// Loaded accounts follow the static keys: every table's writable entries, then
+    /// every table's read-only entries, as the runtime orders them.
+    pub fn resolve_lookups(&mut self, tables: &HashMap<Pubkey, Vec<Pubkey>>) -> Result<()> {
+        let mut loaded = LoadedAddresses::default();
+        for lookup in &self.address_table_lookups {
+            let table = tables
+                .get(&lookup.account_key)
+                .ok_or_else(|| anyhow!("Address lookup table {} not loaded", lookup.account_key))?;
+            let resolve = |indexes: &[u8]| {
+                indexes
+                    .iter()
+                    .map(|&i| {
+                        table.get(i as usize).copied().ok_or_else(|| {
+                            anyhow!(
+                                "Index {} out of range for address lookup table {}",
+                                i,
+                                lookup.account_key
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            };
+            loaded.writable.extend(resolve(&lookup.writable_indexes)?);
+            loaded.readonly.extend(resolve(&lookup.readonly_indexes)?);
+        }
+
+        self.loaded_addresses = loaded;
+        Ok(())
+    }
+
+    /// The account at `index` in the full key list: static keys, then loaded
+    /// writable and loaded read-only addresses.
+    pub fn account_key(&self, index: usize) -> Option<Pubkey> {
+        self.account_keys
+            .iter()
+            .chain(&self.loaded_addresses.writable)
+            .chain(&self.loaded_addresses.readonly)
+            .nth(index)
+            .copied()
+    }
+
+    fn num_account_keys(&self) -> usize {
+        self.account_keys.len() + self.loaded_addresses.len()
+    }
+
+    /// Whether the account at `index` is writable according to the message header,
+    /// or, for a loaded address, the lookup it came from.
+    /// Program ids invoked by an instruction are demoted to read-only, as the runtime does.
+    pub fn is_writable(&self, index: usize) -> bool {
+        if index >= self.account_keys.len() {
+            let loaded = index - self.account_keys.len();
+            return loaded < self.loaded_addresses.writable.len() && !self.is_invoked(index);
+        }
+
+        let num_signed = self.header.num_required_signatures as usize;
+        let writable_by_header = if index < num_signed {
+            index < num_signed.saturating_sub(self.header.num_readonly_signed_accounts as usize)
+        } else {
+            index
+                < self
+                    .account_keys
+                    .len()
+                    .saturating_sub(self.header.num_readonly_unsigned_accounts as usize)
+        };
+
+        writable_by_header && !self.is_invoked(index)
+    }
+
+    /// Every account the transaction may write to, in message order.
+    pub fn writable_accounts(&self) -> Vec<Pubkey> {
+        (0..self.num_account_keys())
+            .filter(|&i| self.is_writable(i))
+            .filter_map(|i| self.account_key(i))
+            .collect()
+    }
+
+    /// The transaction id: its first signature, or `None` while it is unsigned.
+    pub fn signature(&self) -> Option<Signature> {
+        self.signatures
+            .first()
+            .copied()
+            .filter(|signature| *signature != Signature::default())
+    }
+
+    /// The account that pays the transaction fee: always the first account key.
+    pub fn fee_payer(&self) -> Option<Pubkey> {
+        self.account_keys.first().copied()
+    }
+
+    /// The program invoked by `ix`.
+    pub fn program_id(&self, ix: &CompiledInstruction) -> Option<Pubkey> {
+        self.account_key(ix.program_id_index as usize)
+    }
+
+    /// Decoded Compute Budget instructions, in message order.
+    pub fn compute_budget_instructions(&self) -> Vec<ComputeBudgetInstruction> {
+        self.instructions
+            .iter()
+            .filter(|ix| self.program_id(ix) == Some(COMPUTE_BUDGET_PROGRAM_ID))
+            .filter_map(|ix| ComputeBudgetInstruction::unpack(&ix.data))
+            .collect()
+    }
+
+    fn is_invoked(&self, index: usize) -> bool {
+        self.instructions
+            .iter()
+            .any(|ix| ix.program_id_index as usize == index)
+    }
+}
+
+/// Decode a base64-encoded, bincode-serialized legacy or v0 transaction.
+/// Lookup tables of a v0 message are left for `resolve_lookups`.
+pub fn decode_transaction(tx_base64: &str) -> Result<DecodedTransaction> {
+    let bytes = STANDARD
+        .decode(tx_base64)
+        .map_err(|e| anyhow!("Invalid base64 transaction: {}", e))?;
+
+    let tx: VersionedTransaction =
+        bincode::deserialize(&bytes).map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+
+    Ok(tx.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::v0;
+    use solana_sdk::transaction::Transaction;
+
+    fn encode<T: serde::Serialize>(tx: &T) -> String {
+        STANDARD.encode(bincode::serialize(tx).unwrap())
+    }
+
+    #[test]
+    fn writable_accounts_follow_header() {
+        let payer = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let config = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+
+        let ix = Instruction {
+            program_id: program,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(config, false),
+            ],
+            data: vec![],
+        };
+        let tx = Transaction::new_with_payer(&[ix], Some(&payer));
+
+        let decoded = decode_transaction(&encode(&tx)).unwrap();
+        let writable = decoded.writable_accounts();
+
+        assert_eq!(writable, vec![payer, vault]);
+    }
+
+    #[test]
+    fn invoked_program_is_not_writable() {
+        let payer = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+
+        // Listing the program as a writable account meta must not make it writable
+        let ix = Instruction {
+            program_id: program,
+            accounts: vec![AccountMeta::new(program, false)],
+            data: vec![],
+        };
+        let tx = Transaction::new_with_payer(&[ix], Some(&payer));
+
+        let decoded = decode_transaction(&encode(&tx)).unwrap();
+        assert_eq!(decoded.writable_accounts(), vec![payer]);
+    }
+
+    #[test]
+    fn unsigned_transaction_has_no_signature() {
+        let payer = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let tx = Transaction::new_with_payer(&[ix], Some(&payer));
+
+        let decoded = decode_transaction(&encode(&tx)).unwrap();
+        assert_eq!(decoded.signature(), None);
+    }
+
+    #[test]
+    fn resolves_lookup_table_accounts() {
+        let payer = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let table_key = Pubkey::new_unique();
+        let (vault, oracle) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer, program],
+            recent_blockhash: Hash::new_unique(),
+            // Accounts: 2 = vault (loaded writable), 3 = oracle (loaded read-only)
+            instructions: vec![CompiledInstruction::new_from_raw_parts(
+                1,
+                vec![],
+                vec![2, 3],
+            )],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: table_key,
+                writable_indexes: vec![1],
+                readonly_indexes: vec![0],
+            }],
+        };
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(message),
+        };
+
+        let mut decoded = decode_transaction(&encode(&tx)).unwrap();
+        assert!(decoded.has_unresolved_lookups());
+        assert_eq!(decoded.fee_payer(), Some(payer));
+        assert_eq!(decoded.writable_accounts(), vec![payer]);
+
+        let tables = HashMap::from([(table_key, vec![oracle, vault])]);
+        decoded.resolve_lookups(&tables).unwrap();
+
+        assert!(!decoded.has_unresolved_lookups());
+        assert_eq!(decoded.account_key(2), Some(vault));
+        assert_eq!(decoded.account_key(3), Some(oracle));
+        assert_eq!(decoded.program_id(&decoded.instructions[0]), Some(program));
+        assert_eq!(decoded.writable_accounts(), vec![payer, vault]);
+    }
+
+    #[test]
+    fn missing_lookup_table_is_an_error() {
+        let mut decoded = decode_transaction(&encode(&Transaction::new_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[],
+                vec![],
+            )],
+            Some(&Pubkey::new_unique()),
+        )))
+        .unwrap();
+        decoded
+            .address_table_lookups
+            .push(MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            });
+
+        assert!(decoded.resolve_lookups(&HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_base64() {
+        assert!(decode_transaction("not base64!").is_err());
+    }
+}
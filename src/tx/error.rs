@@ -0,0 +1,390 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::Value as Json;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A transaction error as reported by `simulateTransaction` or `getSignatureStatuses`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    /// An instruction failed; `index` is its position in the message.
+    InstructionError { index: u8, error: InstructionError },
+    /// Any other transaction-level error, e.g. `AccountNotFound`.
+    Transaction {
+        name: String,
+        detail: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InstructionError {
+    /// A program-defined error code, named when an IDL or a known table covers it.
+    Custom { code: u32, name: Option<String> },
+    /// A runtime error such as `InvalidAccountData` or `ComputationalBudgetExceeded`.
+    Builtin {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+}
+
+impl TransactionError {
+    /// Parse the `err` JSON value, e.g. `{"InstructionError":[2,{"Custom":2006}]}`.
+    pub fn from_json(value: &Json) -> Self {
+        let (name, detail) = match split_variant(value) {
+            Some(parts) => parts,
+            None => {
+                return TransactionError::Transaction {
+                    name: value.to_string(),
+                    detail: None,
+                }
+            }
+        };
+
+        if name == "InstructionError" {
+            let parts = detail.and_then(|d| d.as_array());
+            let index = parts
+                .and_then(|p| p.first())
+                .and_then(|v| v.as_u64())
+                .and_then(|i| u8::try_from(i).ok());
+            if let (Some(index), Some(error)) = (index, parts.and_then(|p| p.get(1))) {
+                return TransactionError::InstructionError {
+                    index,
+                    error: InstructionError::from_json(error),
+                };
+            }
+        }
+
+        TransactionError::Transaction {
+            name: name.to_string(),
+            detail: detail.map(|d| d.to_string()),
+        }
+    }
+
+    /// The failing instruction's index, for instruction errors.
+    pub fn instruction_index(&self) -> Option<u8> {
+        match self {
+            TransactionError::InstructionError { index, .. } => Some(*index),
+            TransactionError::Transaction { .. } => None,
+        }
+    }
+
+    /// Whether the transaction ran out of compute units.
+    pub fn is_compute_exhausted(&self) -> bool {
+        matches!(
+            self,
+            TransactionError::InstructionError {
+                error: InstructionError::Builtin { name, .. },
+                ..
+            } if name == "ComputationalBudgetExceeded"
+        )
+    }
+
+    /// Name a `Custom` code, if it is not named already.
+    pub fn resolve_name(&mut self, names: &ErrorNames) {
+        if let TransactionError::InstructionError {
+            index,
+            error: InstructionError::Custom { code, name },
+        } = self
+        {
+            if name.is_none() {
+                *name = names.name(*index, *code);
+            }
+        }
+    }
+}
+
+impl InstructionError {
+    fn from_json(value: &Json) -> Self {
+        match split_variant(value) {
+            Some(("Custom", Some(code))) if code.as_u64().is_some() => InstructionError::Custom {
+                code: code.as_u64().unwrap_or_default() as u32,
+                name: None,
+            },
+            Some((name, detail)) => InstructionError::Builtin {
+                name: name.to_string(),
+                detail: detail.map(|d| d.to_string()),
+            },
+            None => InstructionError::Builtin {
+                name: value.to_string(),
+                detail: None,
+            },
+        }
+    }
+}
+
+// Serialized with its rendered message, so JSON consumers get the same line as the text report
+impl Serialize for TransactionError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("TransactionError", 4)?;
+        match self {
+            TransactionError::InstructionError { index, error } => {
+                state.serialize_field("kind", "instruction_error")?;
+                state.serialize_field("index", index)?;
+                state.serialize_field("error", error)?;
+            }
+            TransactionError::Transaction { name, detail } => {
+                state.serialize_field("kind", "transaction")?;
+                state.serialize_field("name", name)?;
+                if let Some(detail) = detail {
+                    state.serialize_field("detail", detail)?;
+                }
+            }
+        }
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+/// Split a serde-encoded enum into its variant name and payload:
+/// `"Name"` or `{"Name": payload}`.
+fn split_variant(value: &Json) -> Option<(&str, Option<&Json>)> {
+    match value {
+        Json::String(name) => Some((name, None)),
+        Json::Object(map) if map.len() == 1 => {
+            let (name, payload) = map.iter().next()?;
+            Some((name, Some(payload)))
+        }
+        _ => None,
+    }
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::InstructionError { index, error } => {
+                write!(f, "Instruction {} failed: {}", index, error)
+            }
+            TransactionError::Transaction { name, detail: None } => write!(f, "{}", name),
+            TransactionError::Transaction {
+                name,
+                detail: Some(detail),
+            } => write!(f, "{} {}", name, detail),
+        }
+    }
+}
+
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstructionError::Custom {
+                code,
+                name: Some(name),
+            } => write!(f, "{} ({})", name, code),
+            InstructionError::Custom { code, name: None } => {
+                write!(f, "custom program error {} (0x{:x})", code, code)
+            }
+            InstructionError::Builtin { name, detail: None } => write!(f, "{}", name),
+            InstructionError::Builtin {
+                name,
+                detail: Some(detail),
+            } => write!(f, "{} {}", name, detail),
+        }
+    }
+}
+
+/// First code of the range Anchor reserves for program-defined errors.
+pub const ANCHOR_ERROR_CODE_OFFSET: u32 = 6000;
+
+/// Names of Anchor's framework error codes (below 6000).
+pub fn anchor_error_name(code: u32) -> Option<&'static str> {
+    let name = match code {
+        100 => "InstructionMissing",
+        101 => "InstructionFallbackNotFound",
+        102 => "InstructionDidNotDeserialize",
+        103 => "InstructionDidNotSerialize",
+        1000 => "IdlInstructionStub",
+        1001 => "IdlInstructionInvalidProgram",
+        1002 => "IdlAccountNotEmpty",
+        1500 => "EventInstructionStub",
+        2000 => "ConstraintMut",
+        2001 => "ConstraintHasOne",
+        2002 => "ConstraintSigner",
+        2003 => "ConstraintRaw",
+        2004 => "ConstraintOwner",
+        2005 => "ConstraintRentExempt",
+        2006 => "ConstraintSeeds",
+        2007 => "ConstraintExecutable",
+        2008 => "ConstraintState",
+        2009 => "ConstraintAssociated",
+        2010 => "ConstraintAssociatedInit",
+        2011 => "ConstraintClose",
+        2012 => "ConstraintAddress",
+        2013 => "ConstraintZero",
+        2014 => "ConstraintTokenMint",
+        2015 => "ConstraintTokenOwner",
+        2016 => "ConstraintMintMintAuthority",
+        2017 => "ConstraintMintFreezeAuthority",
+        2018 => "ConstraintMintDecimals",
+        2019 => "ConstraintSpace",
+        2020 => "ConstraintAccountIsNone",
+        2021 => "ConstraintTokenTokenProgram",
+        2022 => "ConstraintMintTokenProgram",
+        2023 => "ConstraintAssociatedTokenTokenProgram",
+        2500 => "RequireViolated",
+        2501 => "RequireEqViolated",
+        2502 => "RequireKeysEqViolated",
+        2503 => "RequireNeqViolated",
+        2504 => "RequireKeysNeqViolated",
+        2505 => "RequireGtViolated",
+        2506 => "RequireGteViolated",
+        3000 => "AccountDiscriminatorAlreadySet",
+        3001 => "AccountDiscriminatorNotFound",
+        3002 => "AccountDiscriminatorMismatch",
+        3003 => "AccountDidNotDeserialize",
+        3004 => "AccountDidNotSerialize",
+        3005 => "AccountNotEnoughKeys",
+        3006 => "AccountNotMutable",
+        3007 => "AccountOwnedByWrongProgram",
+        3008 => "InvalidProgramId",
+        3009 => "InvalidProgramExecutable",
+        3010 => "AccountNotSigner",
+        3011 => "AccountNotSystemOwned",
+        3012 => "AccountNotInitialized",
+        3013 => "AccountNotProgramData",
+        3014 => "AccountNotAssociatedTokenAccount",
+        3015 => "AccountSysvarMismatch",
+        3016 => "AccountReallocExceedsLimit",
+        3017 => "AccountDuplicateReallocs",
+        4100 => "DeclaredProgramIdMismatch",
+        4101 => "TryingToInitPayerAsProgramAccount",
+        4102 => "InvalidNumericConversion",
+        5000 => "Deprecated",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Program-defined error names from an IDL's `errors` section, keyed by code.
+pub type ErrorCodes = HashMap<u32, String>;
+
+/// Where custom error codes get their names.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorNames {
+    /// IDL error codes, scoped to the IDL's program when it declares an address.
+    pub idls: Vec<(Option<Pubkey>, ErrorCodes)>,
+    /// Program invoked by each top-level instruction, when the transaction was decoded.
+    pub instruction_programs: Vec<Pubkey>,
+}
+
+impl ErrorNames {
+    /// Name `code` as raised by instruction `index`: IDL codes first, then
+    /// Anchor's framework codes.
+    pub fn name(&self, index: u8, code: u32) -> Option<String> {
+        let program = self.instruction_programs.get(index as usize);
+        self.idls
+            .iter()
+            .filter(|(address, _)| match (address, program) {
+                (Some(address), Some(program)) => address == program,
+                _ => true,
+            })
+            .find_map(|(_, codes)| codes.get(&code).cloned())
+            .or_else(|| {
+                (code < ANCHOR_ERROR_CODE_OFFSET)
+                    .then(|| anchor_error_name(code))
+                    .flatten()
+                    .map(String::from)
+            })
+    }
+}
+
+/// Parse an IDL `errors` section: `[{"code": 6000, "name": "...", "msg": "..."}]`.
+pub fn parse_error_codes(errors: &[Json]) -> ErrorCodes {
+    errors
+        .iter()
+        .filter_map(|e| {
+            let code = e.get("code")?.as_u64()?;
+            let name = e.get("name")?.as_str()?;
+            Some((u32::try_from(code).ok()?, name.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_custom_instruction_error() {
+        let mut err =
+            TransactionError::from_json(&json!({"InstructionError": [2, {"Custom": 2006}]}));
+        assert_eq!(err.instruction_index(), Some(2));
+        assert_eq!(
+            err.to_string(),
+            "Instruction 2 failed: custom program error 2006 (0x7d6)"
+        );
+
+        err.resolve_name(&ErrorNames::default());
+        assert_eq!(
+            err.to_string(),
+            "Instruction 2 failed: ConstraintSeeds (2006)"
+        );
+    }
+
+    #[test]
+    fn serializes_with_message() {
+        let mut err =
+            TransactionError::from_json(&json!({"InstructionError": [2, {"Custom": 2006}]}));
+        err.resolve_name(&ErrorNames::default());
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({
+                "kind": "instruction_error",
+                "index": 2,
+                "error": {"kind": "custom", "code": 2006, "name": "ConstraintSeeds"},
+                "message": "Instruction 2 failed: ConstraintSeeds (2006)"
+            })
+        );
+    }
+
+    #[test]
+    fn parses_builtin_and_transaction_errors() {
+        let err = TransactionError::from_json(
+            &json!({"InstructionError": [0, "ComputationalBudgetExceeded"]}),
+        );
+        assert!(err.is_compute_exhausted());
+        assert_eq!(
+            err.to_string(),
+            "Instruction 0 failed: ComputationalBudgetExceeded"
+        );
+
+        let err = TransactionError::from_json(&json!("AccountNotFound"));
+        assert_eq!(err.instruction_index(), None);
+        assert_eq!(err.to_string(), "AccountNotFound");
+
+        let err =
+            TransactionError::from_json(&json!({"InsufficientFundsForRent": {"account_index": 2}}));
+        assert_eq!(
+            err.to_string(),
+            r#"InsufficientFundsForRent {"account_index":2}"#
+        );
+    }
+
+    #[test]
+    fn idl_codes_are_scoped_to_their_program() {
+        let program = Pubkey::new_unique();
+        let names = ErrorNames {
+            idls: vec![(
+                Some(program),
+                HashMap::from([(6000, "Overflow".to_string())]),
+            )],
+            instruction_programs: vec![Pubkey::new_unique(), program],
+        };
+        assert_eq!(names.name(1, 6000).as_deref(), Some("Overflow"));
+        assert_eq!(names.name(0, 6000), None);
+        assert_eq!(names.name(0, 2012).as_deref(), Some("ConstraintAddress"));
+    }
+
+    #[test]
+    fn parses_idl_error_codes() {
+        let codes = parse_error_codes(&[
+            json!({"code": 6000, "name": "Overflow", "msg": "Counter overflowed"}),
+            json!({"name": "missing code"}),
+        ]);
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[&6000], "Overflow");
+    }
+}
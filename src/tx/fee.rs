@@ -0,0 +1,135 @@
+use crate::tx::compute_budget::{
+    default_compute_unit_limit, ComputeBudgetInstruction, MAX_COMPUTE_UNIT_LIMIT,
+};
+use crate::tx::decode::DecodedTransaction;
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+/// Base fee charged per required signature.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// The fee a transaction is expected to cost its fee payer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FeeEstimate {
+    pub fee_payer: Pubkey,
+    /// Signature fees: `LAMPORTS_PER_SIGNATURE` per required signature.
+    pub base_fee: u64,
+    /// Compute unit price times the compute unit limit, rounded up to whole lamports.
+    pub priority_fee: u64,
+}
+
+impl FeeEstimate {
+    pub fn from_transaction(tx: &DecodedTransaction) -> Option<Self> {
+        let fee_payer = tx.fee_payer()?;
+        let base_fee = LAMPORTS_PER_SIGNATURE * tx.header.num_required_signatures as u64;
+
+        let budget = tx.compute_budget_instructions();
+        let price = budget.iter().find_map(|ix| match ix {
+            ComputeBudgetInstruction::SetComputeUnitPrice(price) => Some(*price),
+            _ => None,
+        });
+        let priority_fee = price.map_or(0, |price| {
+            let units = compute_unit_limit(tx) as u128;
+            (price as u128 * units).div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64
+        });
+
+        Some(Self {
+            fee_payer,
+            base_fee,
+            priority_fee,
+        })
+    }
+
+    pub fn total(&self) -> u64 {
+        self.base_fee.saturating_add(self.priority_fee)
+    }
+}
+
+/// The compute unit limit the transaction runs under: the requested limit, or the
+/// per-instruction default (smaller for builtin programs) summed over every instruction.
+pub fn compute_unit_limit(tx: &DecodedTransaction) -> u32 {
+    let requested = tx
+        .compute_budget_instructions()
+        .into_iter()
+        .find_map(|ix| match ix {
+            ComputeBudgetInstruction::SetComputeUnitLimit(limit) => Some(limit),
+            _ => None,
+        });
+
+    requested
+        .unwrap_or_else(|| {
+            default_compute_unit_limit(tx.instructions.iter().filter_map(|ix| tx.program_id(ix)))
+        })
+        .min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::compute_budget::COMPUTE_BUDGET_PROGRAM_ID;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::Message;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+    fn decoded(instructions: &[Instruction], payer: &Pubkey) -> DecodedTransaction {
+        let tx = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+        VersionedTransaction::from(tx).into()
+    }
+
+    fn budget_ix(ix: ComputeBudgetInstruction) -> Instruction {
+        Instruction::new_with_bytes(COMPUTE_BUDGET_PROGRAM_ID, &ix.pack(), vec![])
+    }
+
+    #[test]
+    fn base_fee_only_without_price() {
+        let payer = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let fee = FeeEstimate::from_transaction(&decoded(&[ix], &payer)).unwrap();
+
+        assert_eq!(fee.fee_payer, payer);
+        assert_eq!(fee.base_fee, 5_000);
+        assert_eq!(fee.priority_fee, 0);
+        assert_eq!(fee.total(), 5_000);
+    }
+
+    #[test]
+    fn priority_fee_uses_requested_or_default_limit() {
+        let payer = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+
+        let with_limit = decoded(
+            &[
+                budget_ix(ComputeBudgetInstruction::SetComputeUnitLimit(300_000)),
+                budget_ix(ComputeBudgetInstruction::SetComputeUnitPrice(10_001)),
+                ix.clone(),
+            ],
+            &payer,
+        );
+        // 300_000 * 10_001 micro-lamports = 3_000.3 lamports, rounded up
+        assert_eq!(
+            FeeEstimate::from_transaction(&with_limit)
+                .unwrap()
+                .priority_fee,
+            3_001
+        );
+
+        let default_limit = decoded(
+            &[
+                budget_ix(ComputeBudgetInstruction::SetComputeUnitPrice(1_000_000)),
+                ix.clone(),
+                ix,
+            ],
+            &payer,
+        );
+        // Two program instructions plus the builtin Compute Budget instruction
+        assert_eq!(compute_unit_limit(&default_limit), 403_000);
+        assert_eq!(
+            FeeEstimate::from_transaction(&default_limit)
+                .unwrap()
+                .priority_fee,
+            403_000
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+pub const ADDRESS_LOOKUP_TABLE_PROGRAM_ID: Pubkey =
+    pubkey!("AddressLookupTab1e1111111111111111111111111");
+
+/// Size of the table metadata preceding the addresses: type tag, deactivation
+/// slot, last extended slot and start index, optional authority and padding.
+pub const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+const LOOKUP_TABLE_STATE_TAG: u32 = 1;
+
+/// Decode the addresses stored in an address lookup table account.
+pub fn lookup_table_addresses(data: &[u8]) -> Result<Vec<Pubkey>> {
+    let tag = data
+        .get(..4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("Lookup table data too short"))?;
+    if tag != LOOKUP_TABLE_STATE_TAG {
+        return Err(anyhow!("Lookup table is not initialized"));
+    }
+
+    let addresses = data
+        .get(LOOKUP_TABLE_META_SIZE..)
+        .ok_or_else(|| anyhow!("Lookup table data too short"))?;
+    if addresses.len() % 32 != 0 {
+        return Err(anyhow!("Lookup table address list is misaligned"));
+    }
+
+    Ok(addresses
+        .chunks_exact(32)
+        .map(|chunk| Pubkey::new_from_array(chunk.try_into().unwrap()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup_table_data(addresses: &[Pubkey]) -> Vec<u8> {
+        let mut data = vec![0u8; LOOKUP_TABLE_META_SIZE];
+        data[..4].copy_from_slice(&LOOKUP_TABLE_STATE_TAG.to_le_bytes());
+        for address in addresses {
+            data.extend(address.to_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn decodes_addresses_after_metadata() {
+        let addresses = [Pubkey::new_unique(), Pubkey::new_unique()];
+        assert_eq!(
+            lookup_table_addresses(&lookup_table_data(&addresses)).unwrap(),
+            addresses
+        );
+    }
+
+    #[test]
+    fn rejects_uninitialized_table() {
+        let mut data = lookup_table_data(&[Pubkey::new_unique()]);
+        data[0] = 0;
+        assert!(lookup_table_addresses(&data).is_err());
+        assert!(lookup_table_addresses(&[1, 0]).is_err());
+    }
+}
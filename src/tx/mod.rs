@@ -0,0 +1,8 @@
+pub mod compute_budget;
+pub mod decode;
+pub mod error;
+pub mod fee;
+pub mod lookup_table;
+pub mod nonce;
+pub mod recorded;
+pub mod replay;
@@ -0,0 +1,147 @@
+use crate::tx::decode::DecodedTransaction;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+
+pub const SYSTEM_PROGRAM_ID: Pubkey = pubkey!("11111111111111111111111111111111");
+
+/// `SystemInstruction::AdvanceNonceAccount` variant tag (bincode u32).
+const ADVANCE_NONCE_ACCOUNT_TAG: u32 = 4;
+
+/// Size of a nonce account: version, state, authority, nonce and fee calculator.
+pub const NONCE_ACCOUNT_LEN: usize = 80;
+
+const NONCE_STATE_INITIALIZED: u32 = 1;
+
+/// A transaction that uses a durable nonce instead of a recent blockhash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurableNonce {
+    pub nonce_account: Pubkey,
+    pub authority: Option<Pubkey>,
+    /// The nonce the transaction was signed with, carried in its recent blockhash field.
+    pub nonce: Hash,
+}
+
+/// Detect a durable nonce transaction: the runtime only honours
+/// `AdvanceNonceAccount` as the first instruction.
+pub fn durable_nonce(tx: &DecodedTransaction) -> Option<DurableNonce> {
+    let ix = tx.instructions.first()?;
+    if tx.program_id(ix)? != SYSTEM_PROGRAM_ID {
+        return None;
+    }
+
+    let tag = u32::from_le_bytes(ix.data.get(..4)?.try_into().ok()?);
+    if tag != ADVANCE_NONCE_ACCOUNT_TAG {
+        return None;
+    }
+
+    // Accounts: nonce account, RecentBlockhashes sysvar, nonce authority
+    let key = |i: usize| {
+        ix.accounts
+            .get(i)
+            .and_then(|&index| tx.account_key(index as usize))
+    };
+
+    Some(DurableNonce {
+        nonce_account: key(0)?,
+        authority: key(2),
+        nonce: tx.recent_blockhash,
+    })
+}
+
+/// The initialized state of a nonce account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceState {
+    pub authority: Pubkey,
+    /// The stored nonce a transaction must be signed with.
+    pub nonce: Hash,
+    pub lamports_per_signature: u64,
+}
+
+impl NonceState {
+    /// Decode nonce account data: u32 version, u32 state, then for an initialized
+    /// nonce the authority, the nonce and the fee calculator.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() < NONCE_ACCOUNT_LEN {
+            return None;
+        }
+
+        let state = u32::from_le_bytes(data[4..8].try_into().ok()?);
+        if state != NONCE_STATE_INITIALIZED {
+            return None;
+        }
+
+        Some(Self {
+            authority: Pubkey::new_from_array(data[8..40].try_into().ok()?),
+            nonce: Hash::new_from_array(data[40..72].try_into().ok()?),
+            lamports_per_signature: u64::from_le_bytes(data[72..80].try_into().ok()?),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::Message;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+    pub(crate) fn nonce_data(authority: Pubkey, nonce: Hash) -> Vec<u8> {
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend(NONCE_STATE_INITIALIZED.to_le_bytes());
+        data.extend(authority.to_bytes());
+        data.extend(nonce.to_bytes());
+        data.extend(5_000u64.to_le_bytes());
+        data
+    }
+
+    fn decoded(instructions: &[Instruction], payer: &Pubkey) -> DecodedTransaction {
+        let tx = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+        VersionedTransaction::from(tx).into()
+    }
+
+    fn advance_nonce(nonce_account: Pubkey, authority: Pubkey) -> Instruction {
+        Instruction {
+            program_id: SYSTEM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(nonce_account, false),
+                AccountMeta::new_readonly(
+                    pubkey!("SysvarRecentB1ockHashes11111111111111111111"),
+                    false,
+                ),
+                AccountMeta::new_readonly(authority, true),
+            ],
+            data: ADVANCE_NONCE_ACCOUNT_TAG.to_le_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn detects_advance_nonce_as_first_instruction() {
+        let (payer, nonce_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let other = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+
+        let tx = decoded(
+            &[advance_nonce(nonce_account, payer), other.clone()],
+            &payer,
+        );
+        let nonce = durable_nonce(&tx).unwrap();
+        assert_eq!(nonce.nonce_account, nonce_account);
+        assert_eq!(nonce.authority, Some(payer));
+
+        // Anywhere but first, the runtime treats it as an ordinary instruction
+        let tx = decoded(&[other, advance_nonce(nonce_account, payer)], &payer);
+        assert!(durable_nonce(&tx).is_none());
+    }
+
+    #[test]
+    fn unpacks_initialized_nonce_account() {
+        let authority = Pubkey::new_unique();
+        let nonce = Hash::new_from_array([9; 32]);
+        let state = NonceState::unpack(&nonce_data(authority, nonce)).unwrap();
+
+        assert_eq!(state.authority, authority);
+        assert_eq!(state.nonce, nonce);
+        assert_eq!(state.lamports_per_signature, 5_000);
+        assert!(NonceState::unpack(&[0; NONCE_ACCOUNT_LEN]).is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::tx::decode::{decode_transaction, DecodedTransaction};
+use crate::tx::error::TransactionError;
+use anyhow::{anyhow, Result};
+use serde_json::Value as Json;
+use solana_sdk::pubkey::Pubkey;
+
+/// A landed transaction and its metadata, as returned by `getTransaction`.
+#[derive(Debug, Clone)]
+pub struct RecordedTransaction {
+    pub slot: u64,
+    /// The transaction, with the addresses it loaded from lookup tables at execution.
+    pub transaction: DecodedTransaction,
+    pub error: Option<TransactionError>,
+    /// Fee charged to the fee payer, in lamports.
+    pub fee: u64,
+    /// Lamports of every account key before and after, in account key order.
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    pub pre_token_balances: Vec<TokenBalance>,
+    pub post_token_balances: Vec<TokenBalance>,
+    pub inner_instructions: Vec<InnerInstructions>,
+    pub logs: Vec<String>,
+    pub compute_units_consumed: Option<u64>,
+}
+
+/// Token amount held by a token account, as recorded in the metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub account_index: usize,
+    pub mint: Pubkey,
+    pub owner: Option<Pubkey>,
+    /// Token program that owns the account.
+    pub program_id: Option<Pubkey>,
+    pub amount: u64,
+}
+
+/// The CPIs made by one top-level instruction, in execution order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnerInstructions {
+    pub index: u8,
+    pub instructions: Vec<InnerInstruction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InnerInstruction {
+    pub program_id_index: u8,
+    /// Invocation depth: 2 for a CPI made directly by the top-level instruction.
+    pub stack_height: Option<u32>,
+}
+
+impl RecordedTransaction {
+    /// Parse a `getTransaction` result requested with `base64` encoding.
+    pub fn from_json(value: &Json) -> Result<Self> {
+        let slot = value
+            .get("slot")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| anyhow!("getTransaction response missing 'slot'"))?;
+        let meta = value
+            .get("meta")
+            .filter(|v| !v.is_null())
+            .ok_or_else(|| anyhow!("getTransaction response missing 'meta'"))?;
+        let tx_base64 = value
+            .pointer("/transaction/0")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("getTransaction response missing base64 'transaction'"))?;
+
+        let mut transaction = decode_transaction(tx_base64)?;
+        if let Some(loaded) = meta.get("loadedAddresses") {
+            transaction.loaded_addresses.writable = pubkeys(loaded.get("writable"))?;
+            transaction.loaded_addresses.readonly = pubkeys(loaded.get("readonly"))?;
+        }
+
+        Ok(Self {
+            slot,
+            transaction,
+            error: meta
+                .get("err")
+                .filter(|v| !v.is_null())
+                .map(TransactionError::from_json),
+            fee: meta.get("fee").and_then(|v| v.as_u64()).unwrap_or_default(),
+            pre_balances: balances(meta.get("preBalances"))?,
+            post_balances: balances(meta.get("postBalances"))?,
+            pre_token_balances: token_balances(meta.get("preTokenBalances"))?,
+            post_token_balances: token_balances(meta.get("postTokenBalances"))?,
+            inner_instructions: inner_instructions(meta.get("innerInstructions")),
+            logs: meta
+                .get("logMessages")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            compute_units_consumed: meta.get("computeUnitsConsumed").and_then(|v| v.as_u64()),
+        })
+    }
+}
+
+fn array(value: Option<&Json>) -> impl Iterator<Item = &Json> {
+    value.and_then(|v| v.as_array()).into_iter().flatten()
+}
+
+fn pubkeys(value: Option<&Json>) -> Result<Vec<Pubkey>> {
+    array(value)
+        .map(|v| {
+            v.as_str()
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| anyhow!("Invalid loaded address: {}", v))
+        })
+        .collect()
+}
+
+fn balances(value: Option<&Json>) -> Result<Vec<u64>> {
+    array(value)
+        .map(|v| v.as_u64().ok_or_else(|| anyhow!("Invalid balance: {}", v)))
+        .collect()
+}
+
+fn token_balances(value: Option<&Json>) -> Result<Vec<TokenBalance>> {
+    array(value)
+        .map(|v| token_balance(v).ok_or_else(|| anyhow!("Invalid token balance: {}", v)))
+        .collect()
+}
+
+fn token_balance(value: &Json) -> Option<TokenBalance> {
+    let pubkey = |key: &str| value.get(key)?.as_str()?.parse().ok();
+    Some(TokenBalance {
+        account_index: value.get("accountIndex")?.as_u64()? as usize,
+        mint: pubkey("mint")?,
+        owner: pubkey("owner"),
+        program_id: pubkey("programId"),
+        amount: value
+            .pointer("/uiTokenAmount/amount")?
+            .as_str()?
+            .parse()
+            .ok()?,
+    })
+}
+
+fn inner_instructions(value: Option<&Json>) -> Vec<InnerInstructions> {
+    array(value)
+        .filter_map(|v| {
+            Some(InnerInstructions {
+                index: v.get("index")?.as_u64()? as u8,
+                instructions: array(v.get("instructions"))
+                    .filter_map(|ix| {
+                        Some(InnerInstruction {
+                            program_id_index: ix.get("programIdIndex")?.as_u64()? as u8,
+                            stack_height: ix
+                                .get("stackHeight")
+                                .and_then(|h| h.as_u64())
+                                .map(|h| h as u32),
+                        })
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde_json::json;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::transaction::Transaction;
+
+    #[test]
+    fn parses_get_transaction_result() {
+        let payer = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let ix = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![AccountMeta::new(vault, false)],
+            data: vec![],
+        };
+        let tx = Transaction::new_with_payer(&[ix], Some(&payer));
+        let tx_base64 = STANDARD.encode(bincode::serialize(&tx).unwrap());
+
+        let record = RecordedTransaction::from_json(&json!({
+            "slot": 42,
+            "transaction": [tx_base64, "base64"],
+            "meta": {
+                "err": {"InstructionError": [0, {"Custom": 6000}]},
+                "fee": 5000,
+                "preBalances": [1_000_000, 2_000_000, 1],
+                "postBalances": [995_000, 2_000_000, 1],
+                "preTokenBalances": [{
+                    "accountIndex": 1,
+                    "mint": mint.to_string(),
+                    "owner": payer.to_string(),
+                    "programId": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+                    "uiTokenAmount": {"amount": "100", "decimals": 6}
+                }],
+                "postTokenBalances": [],
+                "innerInstructions": [{
+                    "index": 0,
+                    "instructions": [{"programIdIndex": 2, "accounts": [1], "data": "", "stackHeight": 2}]
+                }],
+                "logMessages": ["Program log: hello"],
+                "loadedAddresses": {"writable": [], "readonly": []},
+                "computeUnitsConsumed": 1234
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(record.slot, 42);
+        assert_eq!(record.fee, 5000);
+        assert_eq!(record.error.unwrap().instruction_index(), Some(0));
+        assert_eq!(record.post_balances, vec![995_000, 2_000_000, 1]);
+        assert_eq!(record.pre_token_balances[0].account_index, 1);
+        assert_eq!(record.pre_token_balances[0].mint, mint);
+        assert_eq!(record.pre_token_balances[0].amount, 100);
+        assert!(record.post_token_balances.is_empty());
+        assert_eq!(
+            record.inner_instructions[0].instructions[0].stack_height,
+            Some(2)
+        );
+        assert_eq!(record.logs, vec!["Program log: hello"]);
+        assert_eq!(record.compute_units_consumed, Some(1234));
+        assert_eq!(record.transaction.fee_payer(), Some(payer));
+    }
+
+    #[test]
+    fn missing_meta_is_an_error() {
+        assert!(RecordedTransaction::from_json(&json!({"slot": 1, "meta": null})).is_err());
+    }
+}
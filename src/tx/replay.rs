@@ -0,0 +1,186 @@
+use crate::tx::compute_budget::{
+    default_compute_unit_limit, ComputeBudgetInstruction, COMPUTE_BUDGET_PROGRAM_ID,
+};
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_sdk::message::compiled_instruction::CompiledInstruction;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// A transaction whose instructions run twice, for simulation only.
+#[derive(Debug, Clone)]
+pub struct DoubledTransaction {
+    pub tx_base64: String,
+    /// For each instruction of the doubled transaction, the original instruction it
+    /// repeats: `None` for the first run and for a pinned compute unit limit.
+    pub repeated_from: Vec<Option<usize>>,
+}
+
+/// Build a transaction that executes the instructions of `tx_base64` twice in a row,
+/// so one simulation shows what a second execution does on top of the first.
+///
+/// Compute Budget instructions are kept once, since the runtime rejects duplicates.
+/// When a compute unit price is set without an explicit limit, the original default
+/// limit is pinned so the priority fee (and with it the fee payer's balance) matches
+/// a single run. Signatures are left untouched; the result is only valid for
+/// simulation with signature verification disabled.
+pub fn repeat_instructions(tx_base64: &str) -> Result<DoubledTransaction> {
+    let bytes = STANDARD
+        .decode(tx_base64)
+        .map_err(|e| anyhow!("Invalid base64 transaction: {}", e))?;
+    let mut tx: VersionedTransaction =
+        bincode::deserialize(&bytes).map_err(|e| anyhow!("Failed to decode transaction: {}", e))?;
+    let (account_keys, instructions) = message_parts(&mut tx.message);
+
+    // Programs are never loaded from lookup tables, so the static keys are enough
+    let compute_budget_index = account_keys
+        .iter()
+        .position(|key| *key == COMPUTE_BUDGET_PROGRAM_ID);
+    let is_compute_budget =
+        |ix: &CompiledInstruction| Some(ix.program_id_index as usize) == compute_budget_index;
+
+    let budget: Vec<_> = instructions
+        .iter()
+        .filter(|ix| is_compute_budget(ix))
+        .filter_map(|ix| ComputeBudgetInstruction::unpack(&ix.data))
+        .collect();
+
+    let (original_indices, repeated): (Vec<_>, Vec<_>) = instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, ix)| !is_compute_budget(ix))
+        .map(|(i, ix)| (Some(i), ix.clone()))
+        .unzip();
+
+    let has_price = budget
+        .iter()
+        .any(|ix| matches!(ix, ComputeBudgetInstruction::SetComputeUnitPrice(_)));
+    let has_limit = budget
+        .iter()
+        .any(|ix| matches!(ix, ComputeBudgetInstruction::SetComputeUnitLimit(_)));
+
+    if let (Some(index), true, false) = (compute_budget_index, has_price, has_limit) {
+        let default_limit = default_compute_unit_limit(
+            instructions
+                .iter()
+                .filter_map(|ix| account_keys.get(ix.program_id_index as usize).copied()),
+        );
+        instructions.push(CompiledInstruction {
+            program_id_index: index as u8,
+            accounts: vec![],
+            data: ComputeBudgetInstruction::SetComputeUnitLimit(default_limit).pack(),
+        });
+    }
+
+    let mut repeated_from = vec![None; instructions.len()];
+    repeated_from.extend(original_indices);
+    instructions.extend(repeated);
+
+    let bytes =
+        bincode::serialize(&tx).map_err(|e| anyhow!("Failed to encode transaction: {}", e))?;
+    Ok(DoubledTransaction {
+        tx_base64: STANDARD.encode(bytes),
+        repeated_from,
+    })
+}
+
+fn message_parts(message: &mut VersionedMessage) -> (&[Pubkey], &mut Vec<CompiledInstruction>) {
+    match message {
+        VersionedMessage::Legacy(m) => (&m.account_keys, &mut m.instructions),
+        VersionedMessage::V0(m) => (&m.account_keys, &mut m.instructions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::decode::decode_transaction;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::v0;
+    use solana_sdk::transaction::Transaction;
+
+    fn increment(program: Pubkey, counter: Pubkey) -> Instruction {
+        Instruction {
+            program_id: program,
+            accounts: vec![AccountMeta::new(counter, false)],
+            data: vec![],
+        }
+    }
+
+    fn budget(ix: ComputeBudgetInstruction) -> Instruction {
+        Instruction {
+            program_id: COMPUTE_BUDGET_PROGRAM_ID,
+            accounts: vec![],
+            data: ix.pack(),
+        }
+    }
+
+    fn encode<T: serde::Serialize>(tx: &T) -> String {
+        STANDARD.encode(bincode::serialize(tx).unwrap())
+    }
+
+    #[test]
+    fn repeats_program_instructions_but_not_compute_budget() {
+        let payer = Pubkey::new_unique();
+        let ixs = [
+            budget(ComputeBudgetInstruction::SetComputeUnitLimit(5_000)),
+            increment(Pubkey::new_unique(), Pubkey::new_unique()),
+        ];
+        let tx = Transaction::new_with_payer(&ixs, Some(&payer));
+
+        let doubled = repeat_instructions(&encode(&tx)).unwrap();
+        let decoded = decode_transaction(&doubled.tx_base64).unwrap();
+
+        assert_eq!(decoded.instructions.len(), 3);
+        assert_eq!(decoded.instructions[0], tx.message.instructions[0]);
+        assert_eq!(decoded.instructions[1], decoded.instructions[2]);
+        assert_eq!(doubled.repeated_from, vec![None, None, Some(1)]);
+        assert_eq!(decoded.account_keys, tx.message.account_keys);
+    }
+
+    #[test]
+    fn pins_default_limit_when_only_price_is_set() {
+        let payer = Pubkey::new_unique();
+        let ixs = [
+            budget(ComputeBudgetInstruction::SetComputeUnitPrice(1_000)),
+            increment(Pubkey::new_unique(), Pubkey::new_unique()),
+        ];
+        let tx = Transaction::new_with_payer(&ixs, Some(&payer));
+
+        let doubled = repeat_instructions(&encode(&tx)).unwrap();
+        let decoded = decode_transaction(&doubled.tx_base64).unwrap();
+
+        assert_eq!(decoded.instructions.len(), 4);
+        assert_eq!(
+            ComputeBudgetInstruction::unpack(&decoded.instructions[2].data),
+            Some(ComputeBudgetInstruction::SetComputeUnitLimit(203_000))
+        );
+        assert_eq!(doubled.repeated_from, vec![None, None, None, Some(1)]);
+    }
+
+    #[test]
+    fn repeats_v0_message_instructions() {
+        let payer = Pubkey::new_unique();
+        let message = v0::Message::try_compile(
+            &payer,
+            &[increment(Pubkey::new_unique(), Pubkey::new_unique())],
+            &[],
+            Hash::new_unique(),
+        )
+        .unwrap();
+        let tx = VersionedTransaction {
+            signatures: vec![Default::default()],
+            message: VersionedMessage::V0(message),
+        };
+
+        let doubled = repeat_instructions(&encode(&tx)).unwrap();
+        let decoded = decode_transaction(&doubled.tx_base64).unwrap();
+
+        assert_eq!(decoded.instructions.len(), 2);
+        assert_eq!(decoded.instructions[0], decoded.instructions[1]);
+        assert_eq!(decoded.account_keys, tx.message.static_account_keys());
+    }
+}
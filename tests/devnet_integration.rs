@@ -19,7 +19,7 @@ use solana_sdk::{
 };
 use solana_system_interface::instruction as system_instruction;
 use solaudit::{
-    analysis::engine::{analyse, RetrySafety},
+    analysis::engine::{analyse, AnalysisContext, RetrySafety, SimulationOutcome},
     models::types::AccountSnapshot,
     rpc::client::SolanaRpc,
 };
@@ -126,7 +126,7 @@ async fn test_devnet_counter_increment_diff() {
     let tx1_b64 = STANDARD.encode(&tx1_bytes);
 
     let sim1 = solaudit
-        .simulate_transaction(&tx1_b64, &counter_pk.to_string())
+        .simulate_transaction(&tx1_b64, &[counter_pk.to_string()])
         .await
         .expect("simulate_transaction for TX1 failed");
 
@@ -140,14 +140,27 @@ async fn test_devnet_counter_increment_diff() {
     }
 
     let after_tx1 = sim1
-        .post_snapshot
+        .post_snapshots
+        .into_iter()
+        .next()
+        .flatten()
         .expect("TX1 simulation returned no post-snapshot");
 
     println!("Post-state (TX1) data_len: {}", after_tx1.data_len);
 
-    let result1 = analyse(before_tx1, after_tx1, sim1.logs);
+    let result1 = analyse(
+        vec![(before_tx1, Some(after_tx1))],
+        SimulationOutcome {
+            error: None,
+            logs: sim1.logs,
+        },
+        &AnalysisContext::default(),
+    );
 
-    assert!(result1.diff.data_len_changed, "data_len_changed");
+    assert!(
+        result1.accounts[0].diff.data_len_changed,
+        "data_len_changed"
+    );
 
     let sig1 = raw
         .send_and_confirm_transaction(&tx1)
@@ -203,7 +216,7 @@ async fn test_devnet_counter_increment_diff() {
     let tx2_b64 = STANDARD.encode(&tx2_bytes);
 
     let sim2 = solaudit
-        .simulate_transaction(&tx2_b64, &counter_pk.to_string())
+        .simulate_transaction(&tx2_b64, &[counter_pk.to_string()])
         .await
         .expect("simulate_transaction for TX2 failed");
 
@@ -217,35 +230,51 @@ async fn test_devnet_counter_increment_diff() {
     }
 
     let after_tx2 = sim2
-        .post_snapshot
+        .post_snapshots
+        .into_iter()
+        .next()
+        .flatten()
         .expect("TX2 simulation returned no post-snapshot");
 
     println!("After TX2:  data={:?}", after_tx2.data);
 
-    let result2 = analyse(before_tx2, after_tx2, sim2.logs);
+    let result2 = analyse(
+        vec![(before_tx2, Some(after_tx2))],
+        SimulationOutcome {
+            error: None,
+            logs: sim2.logs,
+        },
+        &AnalysisContext::default(),
+    );
 
-    println!("TX2 Diff:           {:?}", result2.diff);
+    println!("TX2 Diff:           {:?}", result2.accounts[0].diff);
     println!("TX2 Classification: {:?}", result2.classification.safety);
     println!("TX2 Reasons:        {:?}", result2.classification.reasons);
 
     assert!(
-        !result2.diff.lamports_changed,
+        !result2.accounts[0].diff.lamports_changed,
         "lamports_changed should be false"
     );
-    assert!(!result2.diff.owner_changed, "owner_changed should be false");
     assert!(
-        !result2.diff.executable_changed,
+        !result2.accounts[0].diff.owner_changed,
+        "owner_changed should be false"
+    );
+    assert!(
+        !result2.accounts[0].diff.executable_changed,
         "executable_changed should be false"
     );
     assert!(
-        !result2.diff.data_len_changed,
+        !result2.accounts[0].diff.data_len_changed,
         "data_len_changed should be false"
     );
-    assert!(result2.diff.data_changed, "data_changed should be true");
+    assert!(
+        result2.accounts[0].diff.data_changed,
+        "data_changed should be true"
+    );
 
     assert!(matches!(result2.classification.safety, RetrySafety::Unsafe));
 
-    assert!(result2
+    assert!(result2.accounts[0]
         .classification
         .reasons
         .contains(&"Account data content changed".to_string()));